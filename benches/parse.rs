@@ -0,0 +1,194 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tinyresp::{parse_value, Value, MAX_DEPTH};
+
+/// The nom implementation the parser used before the `memchr` line scanning and the hand-rolled
+/// integer parsing, kept as a baseline. It only supports the types used by the comparisons.
+mod nom_baseline {
+    use nom::{
+        branch::alt,
+        bytes::complete::{tag, take, take_while},
+        character::complete::{i64, u32},
+        multi::count,
+        sequence::{delimited, preceded, terminated},
+        IResult,
+    };
+    use tinyresp::Value;
+
+    fn crlf(input: &str) -> IResult<&str, &str> {
+        tag("\r\n")(input)
+    }
+
+    fn line(input: &str) -> IResult<&str, &str> {
+        terminated(take_while(|c| c != '\r' && c != '\n'), crlf)(input)
+    }
+
+    fn simple_string(input: &str) -> IResult<&str, Value<'_>> {
+        let (input, value) = preceded(tag("+"), line)(input)?;
+        Ok((input, Value::SimpleString(value)))
+    }
+
+    fn integer(input: &str) -> IResult<&str, Value<'_>> {
+        let (input, value) = delimited(tag(":"), i64, crlf)(input)?;
+        Ok((input, Value::Integer(value)))
+    }
+
+    fn bulk_string(input: &str) -> IResult<&str, Value<'_>> {
+        let (input, len) = delimited(tag("$"), u32, crlf)(input)?;
+        let (input, value) = terminated(take(len), crlf)(input)?;
+        Ok((input, Value::BulkString(value)))
+    }
+
+    fn array(input: &str) -> IResult<&str, Value<'_>> {
+        let (input, len) = delimited(tag("*"), u32, crlf)(input)?;
+        let (input, values) = count(value, len as usize)(input)?;
+        Ok((input, Value::Array(values)))
+    }
+
+    pub fn value(input: &str) -> IResult<&str, Value<'_>> {
+        alt((simple_string, integer, bulk_string, array))(input)
+    }
+}
+
+fn small_replies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_replies");
+    for (name, input) in [
+        ("simple_string", "+OK\r\n"),
+        ("simple_error", "-ERR unknown command 'FOO'\r\n"),
+        ("integer", ":1234567890\r\n"),
+        ("bulk_string", "$11\r\nhello world\r\n"),
+        ("null", "_\r\n"),
+        ("double", ",3.14159\r\n"),
+        ("verbatim_string", "=15\r\ntxt:Some string\r\n"),
+        ("command", "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n"),
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse_value(black_box(input)).unwrap()));
+    }
+    group.finish();
+}
+
+fn large_arrays(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_arrays");
+    let integers = format!("*10000\r\n{}", ":1234567890\r\n".repeat(10_000));
+    let strings = format!("*10000\r\n{}", "+OK\r\n".repeat(10_000));
+    let bulks = format!("*10000\r\n{}", "$10\r\n0123456789\r\n".repeat(10_000));
+    for (name, input) in [
+        ("integers", &integers),
+        ("simple_strings", &strings),
+        ("bulk_strings", &bulks),
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse_value(black_box(input)).unwrap()));
+    }
+    group.finish();
+}
+
+fn big_bulk_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("big_bulk_strings");
+    let input = format!("${}\r\n{}\r\n", 1024 * 1024, "x".repeat(1024 * 1024));
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("1mb", |b| {
+        b.iter(|| parse_value(black_box(&input)).unwrap())
+    });
+    let line = format!("+{}\r\n", "x".repeat(1024 * 1024));
+    group.throughput(Throughput::Bytes(line.len() as u64));
+    group.bench_function("1mb_simple_string", |b| {
+        b.iter(|| parse_value(black_box(&line)).unwrap())
+    });
+    group.finish();
+}
+
+fn deep_nesting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deep_nesting");
+    for depth in [10, 100, MAX_DEPTH] {
+        // an array containing an array containing... a single integer
+        let input = format!("{}:1\r\n", "*1\r\n".repeat(depth));
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &input, |b, input| {
+            b.iter(|| parse_value(black_box(input)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn maps_and_sets(c: &mut Criterion) {
+    let mut group = c.benchmark_group("maps_and_sets");
+    let entries: String = (0..1000)
+        .map(|i| {
+            let key = format!("field:{}", i);
+            format!("${}\r\n{}\r\n:{}\r\n", key.len(), key, i)
+        })
+        .collect();
+    let map = format!("%1000\r\n{}", entries);
+    let members: String = (0..1000)
+        .map(|i| {
+            let member = format!("member:{}", i);
+            format!("${}\r\n{}\r\n", member.len(), member)
+        })
+        .collect();
+    let set = format!("~1000\r\n{}", members);
+    for (name, input) in [("map_1000", &map), ("set_1000", &set)] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse_value(black_box(input)).unwrap()));
+    }
+    group.finish();
+}
+
+fn nom_comparison(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nom_comparison");
+    let integers = format!("*10000\r\n{}", ":1234567890\r\n".repeat(10_000));
+    let strings = format!("*10000\r\n{}", "+OK\r\n".repeat(10_000));
+    let bulks = format!("*10000\r\n{}", "$10\r\n0123456789\r\n".repeat(10_000));
+    let big_bulk = format!("${}\r\n{}\r\n", 1024 * 1024, "x".repeat(1024 * 1024));
+    let big_line = format!("+{}\r\n", "x".repeat(1024 * 1024));
+    for (name, input) in [
+        ("integers", &integers),
+        ("simple_strings", &strings),
+        ("bulk_strings", &bulks),
+        ("1mb", &big_bulk),
+        ("1mb_simple_string", &big_line),
+    ] {
+        let expected: Value = parse_value(input).unwrap().1;
+        assert_eq!(nom_baseline::value(input).unwrap(), ("", expected));
+
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("tinyresp", name), input, |b, input| {
+            b.iter(|| parse_value(black_box(input)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("nom", name), input, |b, input| {
+            b.iter(|| nom_baseline::value(black_box(input)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn pipelined(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pipelined");
+    // the replies to a pipeline of 1000 `SET`, `GET`, `INCR` and `HGETALL` commands
+    let input = "+OK\r\n$5\r\nvalue\r\n:42\r\n*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n"
+        .repeat(250);
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("1000_replies", |b| {
+        b.iter(|| {
+            let mut rest = black_box(input.as_str());
+            while !rest.is_empty() {
+                let (remaining, value) = parse_value(rest).unwrap();
+                black_box(value);
+                rest = remaining;
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    small_replies,
+    large_arrays,
+    big_bulk_strings,
+    deep_nesting,
+    maps_and_sets,
+    pipelined,
+    nom_comparison
+);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tinyresp::{frame_len, parse_value, parse_value_spanned};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let parsed = parse_value(input);
+    let spanned = parse_value_spanned(input);
+    assert_eq!(parsed.is_ok(), spanned.is_ok());
+    if let (Ok((rest, value)), Ok((_, spanned))) = (parsed, spanned) {
+        let len = input.len() - rest.len();
+        // every frame accepted by the parser must be measured consistently by `frame_len`
+        assert_eq!(frame_len(data), Ok(Some(len)));
+        assert_eq!(spanned.span, 0..len);
+        assert_eq!(spanned.into_value(), value);
+    }
+});
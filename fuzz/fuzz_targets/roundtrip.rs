@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tinyresp::parse_value;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok((_, value)) = parse_value(input) else {
+        return;
+    };
+    // encoding a parsed value and parsing it again must give back the same value
+    let encoded = value.encode();
+    let encoded = std::str::from_utf8(&encoded).expect("the encoding of a value is valid UTF-8");
+    let (rest, reparsed) = parse_value(encoded).expect("the encoding of a value can be parsed");
+    assert_eq!(rest, "");
+    assert_eq!(reparsed, value);
+});
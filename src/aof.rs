@@ -0,0 +1,884 @@
+//! Reading and writing Redis append-only files (AOF).
+//!
+//! An AOF is a sequence of commands encoded as RESP multibulks. Since Redis 7 the AOF is split in
+//! multiple files (a base file and a set of incremental files) listed in a [Manifest].
+//! Commands can be read with an [AofReader] and written with an [AofWriter].
+//!
+//! The RDB snapshots written at the beginning of the AOF when `aof-use-rdb-preamble` is enabled
+//! (and the base files in RDB format) are skipped: only the commands are read.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::Cursor;
+//! use tinyresp::aof::{AofError, AofReader};
+//!
+//! let aof = b"*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n*3\r\n$3\r\nSET\r\n$1\r\na\r\n$1\r\n1\r\n*2\r\n$3\r\nDEL";
+//! let mut reader = AofReader::new(Cursor::new(&aof[..]));
+//! assert_eq!(reader.next().unwrap().unwrap().name(), Some("SELECT"));
+//! assert_eq!(reader.next().unwrap().unwrap().name(), Some("SET"));
+//! // the last command is incomplete
+//! assert!(matches!(
+//!     reader.next(),
+//!     Some(Err(AofError::Truncated { offset: 50 }))
+//! ));
+//! assert!(reader.next().is_none());
+//! ```
+
+mod rdb;
+
+use crate::{frame_len, Command, Decoder, OwnedValue, Protocol, ToResp, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Represents an error that can occur while reading an AOF
+#[derive(Error, Debug)]
+pub enum AofError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Bad file format at offset {offset}: {reason}")]
+    Corrupted { offset: u64, reason: String },
+    /// The file ends with an incomplete command (or with a `MULTI` without the matching `EXEC`).
+    /// `offset` is the length of the file up to the end of the last valid command.
+    #[error("The file is truncated, the last valid command ends at offset {offset}")]
+    Truncated { offset: u64 },
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+}
+
+/// An iterator over the commands of an AOF.
+///
+/// Iteration stops after the first error. When the file is truncated (like `redis-check-aof` does)
+/// a `MULTI` block that is not terminated by an `EXEC` is considered part of the truncated tail, so the
+/// commands of the block yielded before the [AofError::Truncated] error should be discarded.
+/// Annotations (lines starting with `#`, e.g. the timestamps written with `aof-timestamp-enabled`) are skipped,
+/// and so is the RDB preamble (see [AofReader::rdb_preamble_len]).
+pub struct AofReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// The offset of the first byte of `buffer` in the file
+    offset: u64,
+    /// The length of the RDB preamble, once the beginning of the file has been read
+    preamble: Option<Option<u64>>,
+    /// The offset of the last `MULTI` not yet terminated by an `EXEC`
+    multi: Option<u64>,
+    done: bool,
+}
+
+impl AofReader<BufReader<File>> {
+    /// Opens the AOF at the given path
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(AofReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> AofReader<R> {
+    /// Creates a reader for the AOF read from the given reader
+    pub fn new(reader: R) -> Self {
+        AofReader {
+            reader,
+            buffer: Vec::new(),
+            offset: 0,
+            preamble: None,
+            multi: None,
+            done: false,
+        }
+    }
+
+    /// Returns the length of the RDB snapshot the file starts with, if any.
+    /// The data of the snapshot is not decoded, so the commands read don't include it.
+    pub fn rdb_preamble_len(&self) -> Option<u64> {
+        self.preamble.flatten()
+    }
+
+    /// Returns the offset of the end of the last command read
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn fail(&mut self, error: AofError) -> Option<Result<Command, AofError>> {
+        self.done = true;
+        Some(Err(error))
+    }
+
+    fn corrupted(&mut self, reason: impl Into<String>) -> Option<Result<Command, AofError>> {
+        let offset = self.offset;
+        self.fail(AofError::Corrupted {
+            offset,
+            reason: reason.into(),
+        })
+    }
+
+    /// Skips the RDB snapshot at the beginning of the file
+    fn skip_preamble(&mut self) -> Result<(), AofError> {
+        let buffered = io::Cursor::new(std::mem::take(&mut self.buffer));
+        let mut source = buffered.chain(&mut self.reader);
+        let len = rdb::skip(&mut source).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => AofError::Corrupted {
+                offset: 0,
+                reason: "truncated RDB preamble".to_string(),
+            },
+            io::ErrorKind::InvalidData => AofError::Corrupted {
+                offset: 0,
+                reason: e.to_string(),
+            },
+            _ => AofError::Io(e),
+        })?;
+        let (buffered, _) = source.into_inner();
+        let position = buffered.position() as usize;
+        self.buffer = buffered.into_inner();
+        self.buffer.drain(..position);
+        self.offset = len;
+        self.preamble = Some(Some(len));
+        Ok(())
+    }
+
+    /// Decodes the first `len` bytes of the buffer as a command
+    fn command(&self, len: usize) -> Result<Command, String> {
+        let value = Decoder::new()
+            .feed(&self.buffer[..len])
+            .map_err(|e| e.to_string())?
+            .pop();
+        let Some(OwnedValue::Array(parts)) = value else {
+            return Err("expected a multibulk".to_string());
+        };
+        let mut parts = parts.into_iter().map(|part| match part {
+            OwnedValue::BulkString(part) => Ok(part.into_bytes()),
+            OwnedValue::BulkBytes(part) => Ok(part),
+            _ => Err("expected a bulk string".to_string()),
+        });
+        let name = parts.next().ok_or("empty command")??;
+        parts.try_fold(Command::new(name), |command, part| Ok(command.arg(part?)))
+    }
+}
+
+impl<R: Read> Iterator for AofReader<R> {
+    type Item = Result<Command, AofError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let frame = if self.preamble.is_none() {
+                // wait until there's enough data to tell if the file starts with an RDB snapshot
+                if self.buffer.len() < rdb::MAGIC.len() && rdb::MAGIC.starts_with(&self.buffer) {
+                    None
+                } else {
+                    self.preamble = Some(None);
+                    if self.buffer.starts_with(rdb::MAGIC) {
+                        if let Err(e) = self.skip_preamble() {
+                            return self.fail(e);
+                        }
+                    }
+                    continue;
+                }
+            } else if self.buffer.first() == Some(&b'#') {
+                // annotation
+                match self.buffer.iter().position(|b| *b == b'\n') {
+                    Some(end) => {
+                        self.buffer.drain(..=end);
+                        self.offset += end as u64 + 1;
+                        continue;
+                    }
+                    None => None,
+                }
+            } else {
+                match frame_len(&self.buffer) {
+                    Ok(frame) => frame,
+                    Err(e) => return self.corrupted(e.to_string()),
+                }
+            };
+
+            if let Some(len) = frame {
+                let command = match self.command(len) {
+                    Ok(command) => command,
+                    Err(reason) => return self.corrupted(reason),
+                };
+                match command.name().map(str::to_ascii_uppercase).as_deref() {
+                    Some("MULTI") => self.multi = Some(self.offset),
+                    Some("EXEC") => self.multi = None,
+                    _ => {}
+                }
+                self.buffer.drain(..len);
+                self.offset += len as u64;
+                return Some(Ok(command));
+            }
+
+            let mut chunk = [0u8; 8 * 1024];
+            match self.reader.read(&mut chunk) {
+                Ok(0) if self.buffer.is_empty() && self.multi.is_none() => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(0) => {
+                    let offset = self.multi.unwrap_or(self.offset);
+                    return self.fail(AofError::Truncated { offset });
+                }
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return self.fail(e.into()),
+            }
+        }
+    }
+}
+
+/// Checks the AOF at the given path and, if it's truncated, truncates it to the end of the last valid command
+/// (like `redis-check-aof --fix`). Returns the number of bytes removed, if any.
+///
+/// Files that are corrupted in any other way are not modified and the error is returned.
+pub fn fix(path: impl AsRef<Path>) -> Result<Option<u64>, AofError> {
+    let path = path.as_ref();
+    for command in AofReader::open(path)? {
+        match command {
+            Ok(_) => {}
+            Err(AofError::Truncated { offset }) => {
+                let file = OpenOptions::new().write(true).open(path)?;
+                let len = file.metadata()?.len();
+                file.set_len(offset)?;
+                file.sync_all()?;
+                return Ok(Some(len - offset));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+/// The type of a file listed in a [Manifest]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AofFileType {
+    /// The base file (`b`), containing a snapshot of the dataset (either in RDB or AOF format)
+    Base,
+    /// A file that is not used anymore and that will be deleted by the server (`h`)
+    History,
+    /// An incremental file (`i`), containing the commands executed after the base was created
+    Incr,
+}
+
+/// A file listed in a [Manifest]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AofFile {
+    /// The name of the file, relative to the directory of the manifest
+    pub name: String,
+    pub seq: u64,
+    pub file_type: AofFileType,
+}
+
+/// The manifest of a multi part AOF (Redis 7+), e.g.
+///
+/// ```text
+/// file appendonly.aof.1.base.rdb seq 1 type b
+/// file appendonly.aof.1.incr.aof seq 1 type i
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Manifest {
+    pub files: Vec<AofFile>,
+}
+
+/// Splits a manifest line in arguments (arguments can be quoted, like in `redis.conf`)
+fn split_args(line: &str) -> Result<Vec<String>, AofError> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(first) = chars.next() else {
+            return Ok(args);
+        };
+        let mut arg = String::new();
+        if first == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => arg.push('\n'),
+                        Some('r') => arg.push('\r'),
+                        Some('t') => arg.push('\t'),
+                        Some(c) => arg.push(c),
+                        None => break,
+                    },
+                    Some(c) => arg.push(c),
+                    None => {
+                        return Err(AofError::InvalidManifest(format!(
+                            "unbalanced quotes in line {:?}",
+                            line
+                        )))
+                    }
+                }
+            }
+        } else {
+            arg.push(first);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                arg.push(c);
+            }
+        }
+        args.push(arg);
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = AofError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut files = Vec::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let args = split_args(line)?;
+            let (mut name, mut seq, mut file_type) = (None, None, None);
+            // unknown keys are ignored, like the server does
+            for pair in args.chunks(2) {
+                let [key, value] = pair else {
+                    return Err(AofError::InvalidManifest(format!(
+                        "missing value in line {:?}",
+                        line
+                    )));
+                };
+                match key.as_str() {
+                    "file" => name = Some(value.clone()),
+                    "seq" => seq = value.parse::<u64>().ok(),
+                    "type" => {
+                        file_type = match value.as_str() {
+                            "b" => Some(AofFileType::Base),
+                            "h" => Some(AofFileType::History),
+                            "i" => Some(AofFileType::Incr),
+                            _ => None,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            match (name, seq, file_type) {
+                (Some(name), Some(seq), Some(file_type)) => files.push(AofFile {
+                    name,
+                    seq,
+                    file_type,
+                }),
+                _ => {
+                    return Err(AofError::InvalidManifest(format!(
+                        "invalid line {:?}",
+                        line
+                    )))
+                }
+            }
+        }
+
+        if files
+            .iter()
+            .filter(|file| file.file_type == AofFileType::Base)
+            .count()
+            > 1
+        {
+            return Err(AofError::InvalidManifest(
+                "more than one base file".to_string(),
+            ));
+        }
+        Ok(Manifest { files })
+    }
+}
+
+impl Manifest {
+    /// Reads the manifest at the given path
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AofError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Returns the files to replay, in order: the base file followed by the incremental files
+    /// sorted by sequence number (history files are skipped)
+    pub fn replay_order(&self) -> Vec<&AofFile> {
+        let mut incr: Vec<&AofFile> = self
+            .files
+            .iter()
+            .filter(|file| file.file_type == AofFileType::Incr)
+            .collect();
+        incr.sort_by_key(|file| file.seq);
+        self.files
+            .iter()
+            .filter(|file| file.file_type == AofFileType::Base)
+            .chain(incr)
+            .collect()
+    }
+}
+
+/// Reads the commands of all the files listed in the manifest at the given path, in replay order.
+///
+/// The offsets reported in the errors are relative to the file being read. Base files in RDB format
+/// are skipped, since their data is not decoded.
+pub fn open_manifest(
+    path: impl AsRef<Path>,
+) -> Result<impl Iterator<Item = Result<Command, AofError>>, AofError> {
+    let path = path.as_ref();
+    let manifest = Manifest::load(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let readers = manifest
+        .replay_order()
+        .into_iter()
+        .map(|file| AofReader::open(dir.join(&file.name)))
+        .collect::<Result<Vec<_>, _>>()?;
+    // stop at the first error, the following files can't be replayed
+    let mut failed = false;
+    Ok(readers.into_iter().flatten().take_while(move |command| {
+        let take = !failed;
+        failed |= command.is_err();
+        take
+    }))
+}
+
+/// A writer that can make the written data durable
+pub trait Fsync: Write {
+    /// Flushes the buffered data and makes sure it has reached the storage device
+    fn fsync(&mut self) -> io::Result<()>;
+}
+
+impl Fsync for File {
+    fn fsync(&mut self) -> io::Result<()> {
+        self.sync_data()
+    }
+}
+
+impl<W: Fsync> Fsync for BufWriter<W> {
+    fn fsync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.get_mut().fsync()
+    }
+}
+
+impl Fsync for Vec<u8> {
+    fn fsync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Fsync + ?Sized> Fsync for &mut W {
+    fn fsync(&mut self) -> io::Result<()> {
+        (**self).fsync()
+    }
+}
+
+/// When an [AofWriter] makes the written commands durable
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FsyncPolicy {
+    /// After every command
+    Always,
+    /// After every N commands
+    EveryN(NonZeroUsize),
+    /// Never (the data is only flushed to the operating system when the writer is flushed or dropped)
+    #[default]
+    Never,
+}
+
+/// Writes commands in the AOF format, so that they can be replayed by a Redis server.
+///
+/// Every command is written for a given database: a `SELECT` is inserted whenever the database
+/// changes (including before the first command), like the server does.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::aof::{AofWriter, FsyncPolicy};
+/// use tinyresp::{parse_value, Command};
+///
+/// let mut writer = AofWriter::new(Vec::new(), FsyncPolicy::Never);
+/// writer.write(0, &Command::new("SET").args(["a", "1"])).unwrap();
+/// let (_, value) = parse_value("*2\r\n$3\r\nDEL\r\n$1\r\na\r\n").unwrap();
+/// writer.write_value(0, &value).unwrap();
+/// assert_eq!(
+///     writer.into_inner().unwrap(),
+///     b"*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n*3\r\n$3\r\nSET\r\n$1\r\na\r\n$1\r\n1\r\n*2\r\n$3\r\nDEL\r\n$1\r\na\r\n"
+/// );
+/// ```
+pub struct AofWriter<W: Fsync> {
+    writer: W,
+    policy: FsyncPolicy,
+    db: Option<u32>,
+    /// The number of commands written since the last fsync
+    pending: usize,
+}
+
+impl AofWriter<BufWriter<File>> {
+    /// Opens the AOF at the given path for appending, creating it if it doesn't exist.
+    ///
+    /// The writer doesn't know which database was selected by the commands already in the file,
+    /// so the first command is always preceded by a `SELECT`.
+    pub fn append(path: impl AsRef<Path>, policy: FsyncPolicy) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(AofWriter::new(BufWriter::new(file), policy))
+    }
+}
+
+impl<W: Fsync> AofWriter<W> {
+    /// Creates a writer writing the commands to the given writer
+    pub fn new(writer: W, policy: FsyncPolicy) -> Self {
+        AofWriter {
+            writer,
+            policy,
+            db: None,
+            pending: 0,
+        }
+    }
+
+    /// Writes a command to be executed on the given database
+    pub fn write(&mut self, db: u32, command: &Command) -> io::Result<()> {
+        let mut out = Vec::new();
+        if self.db != Some(db) {
+            Command::new("SELECT")
+                .arg(db.to_string())
+                .write_resp(&mut out, Protocol::Resp2);
+        }
+        command.write_resp(&mut out, Protocol::Resp2);
+        self.writer.write_all(&out)?;
+        self.db = Some(db);
+
+        self.pending += 1;
+        match self.policy {
+            FsyncPolicy::Always => self.fsync(),
+            FsyncPolicy::EveryN(n) if self.pending >= n.get() => self.fsync(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes a command, represented as an array of strings (or integers), to be executed on the given database
+    pub fn write_value(&mut self, db: u32, command: &Value<'_>) -> io::Result<()> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "invalid command");
+        let parts = command
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|part| match part {
+                Value::Integer(i) => Some(i.to_string()),
+                part => part.as_str().map(str::to_string),
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        let (name, args) = parts.split_first().ok_or_else(invalid)?;
+        self.write(db, &Command::new(name).args(args))
+    }
+
+    /// Makes all the commands written so far durable, regardless of the policy
+    pub fn fsync(&mut self) -> io::Result<()> {
+        self.pending = 0;
+        self.writer.fsync()
+    }
+
+    /// Flushes the commands written so far to the underlying writer (without making them durable)
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes the pending commands and returns the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    const SET: &[u8] = b"*3\r\n$3\r\nSET\r\n$1\r\na\r\n$1\r\n1\r\n";
+
+    fn read_all(aof: &[u8]) -> Vec<Result<Command, AofError>> {
+        AofReader::new(Cursor::new(aof)).collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tinyresp-aof-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_read_commands() {
+        let aof = [SET, b"#TS:1628217470\r\n", SET].concat();
+        let commands = read_all(&aof);
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands[1].as_ref().unwrap(),
+            &Command::new("SET").args(["a", "1"])
+        );
+
+        let mut reader = AofReader::new(Cursor::new(&aof));
+        reader.by_ref().for_each(drop);
+        assert_eq!(reader.offset(), aof.len() as u64);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let aof = [SET, &SET[..10]].concat();
+        let commands = read_all(&aof);
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(
+            commands[1],
+            Err(AofError::Truncated { offset: 27 })
+        ));
+
+        // a MULTI without EXEC is part of the truncated tail
+        let aof = [SET, b"*1\r\n$5\r\nMULTI\r\n", SET].concat();
+        let commands = read_all(&aof);
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(
+            commands[3],
+            Err(AofError::Truncated { offset: 27 })
+        ));
+
+        let aof = [SET, b"*1\r\n$5\r\nmulti\r\n", SET, b"*1\r\n$4\r\nEXEC\r\n"].concat();
+        assert!(read_all(&aof).iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let aof = [SET, b"+OK\r\n", SET].concat();
+        let commands = read_all(&aof);
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(
+            &commands[1],
+            Err(AofError::Corrupted { offset: 27, reason }) if reason == "expected a multibulk"
+        ));
+
+        let commands = read_all(b"?\r\n");
+        assert!(matches!(
+            commands[0],
+            Err(AofError::Corrupted { offset: 0, .. })
+        ));
+
+        let commands = read_all(b"REDIS0011\xfa\x09redis-ver");
+        assert!(matches!(
+            &commands[..],
+            [Err(AofError::Corrupted { offset: 0, reason })] if reason == "truncated RDB preamble"
+        ));
+    }
+
+    /// An RDB snapshot containing a single key
+    const RDB: &[u8] = b"REDIS0011\xfe\x00\x00\x01a\x01b\xff\x01\x02\x03\x04\x05\x06\x07\x08";
+
+    #[test]
+    fn test_rdb_preamble() {
+        let aof = [RDB, SET].concat();
+        let mut reader = AofReader::new(Cursor::new(&aof));
+        assert_eq!(
+            reader.next().unwrap().unwrap(),
+            Command::new("SET").args(["a", "1"])
+        );
+        assert!(reader.next().is_none());
+        assert_eq!(reader.rdb_preamble_len(), Some(RDB.len() as u64));
+        assert_eq!(reader.offset(), aof.len() as u64);
+
+        // the offsets of a truncated file include the preamble
+        let aof = [RDB, SET, &SET[..10]].concat();
+        let commands = read_all(&aof);
+        assert!(matches!(
+            commands[1],
+            Err(AofError::Truncated { offset }) if offset == (RDB.len() + SET.len()) as u64
+        ));
+
+        let mut reader = AofReader::new(Cursor::new(SET));
+        reader.by_ref().for_each(drop);
+        assert_eq!(reader.rdb_preamble_len(), None);
+    }
+
+    #[test]
+    fn test_binary_arguments() {
+        let commands = read_all(b"*3\r\n$3\r\nSET\r\n$1\r\na\r\n$2\r\n\xff\x00\r\n");
+        assert_eq!(
+            commands[0].as_ref().unwrap(),
+            &Command::new("SET").arg("a").arg(b"\xff\x00")
+        );
+    }
+
+    #[test]
+    fn test_fix() {
+        let path = temp_dir("fix").join("appendonly.aof");
+        std::fs::write(&path, [SET, SET, &SET[..20]].concat()).unwrap();
+        assert_eq!(fix(&path).unwrap(), Some(20));
+        assert_eq!(std::fs::read(&path).unwrap(), [SET, SET].concat());
+        assert_eq!(fix(&path).unwrap(), None);
+
+        std::fs::write(&path, [SET, b"+OK\r\n"].concat()).unwrap();
+        assert!(matches!(fix(&path), Err(AofError::Corrupted { .. })));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_manifest() {
+        let manifest: Manifest = "file appendonly.aof.2.incr.aof seq 2 type i\n\
+            file \"appendonly.aof.1.base.aof\" seq 1 type b\n\
+            file appendonly.aof.1.incr.aof seq 1 type i\n\
+            file appendonly.aof.0.base.rdb seq 0 type h\n"
+            .parse()
+            .unwrap();
+        assert_eq!(manifest.files.len(), 4);
+        assert_eq!(
+            manifest
+                .replay_order()
+                .iter()
+                .map(|file| file.name.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "appendonly.aof.1.base.aof",
+                "appendonly.aof.1.incr.aof",
+                "appendonly.aof.2.incr.aof"
+            ]
+        );
+
+        assert!(matches!(
+            "file a.aof seq 1".parse::<Manifest>(),
+            Err(AofError::InvalidManifest(_))
+        ));
+        assert!(matches!(
+            "file a seq 1 type b\nfile b seq 2 type b".parse::<Manifest>(),
+            Err(AofError::InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn test_open_manifest() {
+        let dir = temp_dir("manifest");
+        std::fs::write(
+            dir.join("appendonly.aof.manifest"),
+            "file base.aof seq 1 type b\nfile incr.aof seq 1 type i\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("base.aof"), SET).unwrap();
+        std::fs::write(dir.join("incr.aof"), b"*2\r\n$3\r\nDEL\r\n$1\r\na\r\n").unwrap();
+
+        let commands: Vec<Command> = open_manifest(dir.join("appendonly.aof.manifest"))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                Command::new("SET").args(["a", "1"]),
+                Command::new("DEL").arg("a")
+            ]
+        );
+
+        // the default base file of Redis 7 is an RDB snapshot
+        std::fs::write(
+            dir.join("appendonly.aof.manifest"),
+            "file base.rdb seq 1 type b\nfile incr.aof seq 1 type i\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("base.rdb"), RDB).unwrap();
+        let commands: Vec<Command> = open_manifest(dir.join("appendonly.aof.manifest"))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(commands, vec![Command::new("DEL").arg("a")]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// A writer counting the number of fsyncs
+    #[derive(Default)]
+    struct CountingWriter {
+        data: Vec<u8>,
+        fsyncs: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Fsync for CountingWriter {
+        fn fsync(&mut self) -> io::Result<()> {
+            self.fsyncs += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_writer_select() {
+        let mut writer = AofWriter::new(Vec::new(), FsyncPolicy::Never);
+        writer
+            .write(0, &Command::new("SET").args(["a", "1"]))
+            .unwrap();
+        writer.write(0, &Command::new("INCR").arg("a")).unwrap();
+        writer.write(3, &Command::new("DEL").arg("a")).unwrap();
+        writer
+            .write_value(
+                0,
+                &Value::Array(vec![
+                    Value::BulkString("EXPIRE"),
+                    Value::SimpleString("a"),
+                    Value::Integer(10),
+                ]),
+            )
+            .unwrap();
+        assert_eq!(
+            writer
+                .write_value(0, &Value::Array(vec![Value::Null]))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let aof = writer.into_inner().unwrap();
+        let commands: Vec<Command> = read_all(&aof).into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            commands,
+            vec![
+                Command::new("SELECT").arg("0"),
+                Command::new("SET").args(["a", "1"]),
+                Command::new("INCR").arg("a"),
+                Command::new("SELECT").arg("3"),
+                Command::new("DEL").arg("a"),
+                Command::new("SELECT").arg("0"),
+                Command::new("EXPIRE").args(["a", "10"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_writer_fsync_policy() {
+        let fsyncs = |policy| {
+            let mut out = CountingWriter::default();
+            let mut writer = AofWriter::new(&mut out, policy);
+            for _ in 0..5 {
+                writer.write(0, &Command::new("PING")).unwrap();
+            }
+            out.fsyncs
+        };
+        assert_eq!(fsyncs(FsyncPolicy::Always), 5);
+        assert_eq!(
+            fsyncs(FsyncPolicy::EveryN(NonZeroUsize::new(2).unwrap())),
+            2
+        );
+        assert_eq!(
+            fsyncs(FsyncPolicy::EveryN(NonZeroUsize::new(1).unwrap())),
+            5
+        );
+        assert_eq!(fsyncs(FsyncPolicy::Never), 0);
+    }
+
+    #[test]
+    fn test_writer_append() {
+        let path = temp_dir("append").join("appendonly.aof");
+        std::fs::write(&path, SET).unwrap();
+        let mut writer = AofWriter::append(&path, FsyncPolicy::Always).unwrap();
+        writer.write(1, &Command::new("DEL").arg("a")).unwrap();
+        drop(writer);
+
+        let commands: Vec<Command> = AofReader::open(&path)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[1], Command::new("SELECT").arg("1"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+//! Skipping the RDB snapshots found at the beginning of the AOFs written with `aof-use-rdb-preamble`
+//! (and in the base files of multi part AOFs).
+//!
+//! The snapshot is not decoded: its structure is only walked to find where it ends.
+
+use std::io::{self, Read};
+
+/// The bytes every RDB snapshot starts with
+pub(super) const MAGIC: &[u8] = b"REDIS";
+
+/// Reads a whole RDB snapshot (including the checksum) from the reader, returning its length.
+///
+/// Returns an [io::ErrorKind::UnexpectedEof] error if the snapshot is truncated and an
+/// [io::ErrorKind::InvalidData] error if it's malformed or uses an unsupported encoding.
+pub(super) fn skip(reader: impl Read) -> io::Result<u64> {
+    let mut rdb = Rdb { reader, read: 0 };
+    let mut header = [0u8; 9];
+    rdb.read(&mut header)?;
+    let version = std::str::from_utf8(&header[MAGIC.len()..])
+        .ok()
+        .filter(|version| version.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|version| version.parse::<u32>().ok())
+        .filter(|_| header.starts_with(MAGIC))
+        .ok_or_else(|| invalid("invalid RDB header"))?;
+
+    loop {
+        match rdb.byte()? {
+            // EOF, followed by the checksum since version 5
+            0xff => {
+                if version >= 5 {
+                    rdb.skip(8)?;
+                }
+                return Ok(rdb.read);
+            }
+            // SELECTDB
+            0xfe => {
+                rdb.length()?;
+            }
+            // EXPIRETIME
+            0xfd => rdb.skip(4)?,
+            // EXPIRETIME_MS
+            0xfc => rdb.skip(8)?,
+            // RESIZEDB
+            0xfb => {
+                rdb.length()?;
+                rdb.length()?;
+            }
+            // AUX
+            0xfa => {
+                rdb.string()?;
+                rdb.string()?;
+            }
+            // FREQ
+            0xf9 => rdb.skip(1)?,
+            // IDLE
+            0xf8 => {
+                rdb.length()?;
+            }
+            // MODULE_AUX: the module id and when the data was saved, followed by the data
+            0xf7 => {
+                rdb.length()?;
+                rdb.length()?;
+                rdb.length()?;
+                rdb.module_value()?;
+            }
+            // FUNCTION2
+            0xf6 => rdb.string()?,
+            // SLOT_INFO
+            0xf4 => {
+                rdb.length()?;
+                rdb.length()?;
+                rdb.length()?;
+            }
+            value_type => {
+                rdb.string()?;
+                rdb.value(value_type)?;
+            }
+        }
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.into())
+}
+
+struct Rdb<R> {
+    reader: R,
+    /// The number of bytes read so far
+    read: u64,
+}
+
+impl<R: Read> Rdb<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.reader.read_exact(buf)?;
+        self.read += buf.len() as u64;
+        Ok(())
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8; 1];
+        self.read(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.reader).take(len), &mut io::sink())?;
+        self.read += skipped;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    /// Reads a length, or the encoding of a special string (`Err`)
+    fn encoded_length(&mut self) -> io::Result<Result<u64, u8>> {
+        let first = self.byte()?;
+        Ok(match first >> 6 {
+            0 => Ok((first & 0x3f) as u64),
+            1 => Ok(((first & 0x3f) as u64) << 8 | self.byte()? as u64),
+            2 if first == 0x80 => {
+                let mut len = [0u8; 4];
+                self.read(&mut len)?;
+                Ok(u32::from_be_bytes(len) as u64)
+            }
+            2 if first == 0x81 => {
+                let mut len = [0u8; 8];
+                self.read(&mut len)?;
+                Ok(u64::from_be_bytes(len))
+            }
+            2 => return Err(invalid(format!("invalid RDB length 0x{:02x}", first))),
+            _ => Err(first & 0x3f),
+        })
+    }
+
+    fn length(&mut self) -> io::Result<u64> {
+        self.encoded_length()?
+            .map_err(|_| invalid("unexpected RDB string encoding"))
+    }
+
+    fn string(&mut self) -> io::Result<()> {
+        match self.encoded_length()? {
+            Ok(len) => self.skip(len),
+            // integers
+            Err(0) => self.skip(1),
+            Err(1) => self.skip(2),
+            Err(2) => self.skip(4),
+            // LZF: the compressed length and the uncompressed length, followed by the data
+            Err(3) => {
+                let compressed = self.length()?;
+                self.length()?;
+                self.skip(compressed)
+            }
+            Err(encoding) => Err(invalid(format!("invalid RDB string encoding {}", encoding))),
+        }
+    }
+
+    /// Skips a double in the legacy format (a length followed by its textual representation)
+    fn legacy_double(&mut self) -> io::Result<()> {
+        match self.byte()? {
+            // infinities and NaN
+            253..=255 => Ok(()),
+            len => self.skip(len as u64),
+        }
+    }
+
+    fn repeat(&mut self, f: impl Fn(&mut Self) -> io::Result<()>) -> io::Result<()> {
+        for _ in 0..self.length()? {
+            f(self)?;
+        }
+        Ok(())
+    }
+
+    /// Skips the data written by a module (a sequence of typed values terminated by EOF)
+    fn module_value(&mut self) -> io::Result<()> {
+        loop {
+            match self.length()? {
+                // EOF
+                0 => return Ok(()),
+                // SINT and UINT
+                1 | 2 => {
+                    self.length()?;
+                }
+                // FLOAT
+                3 => self.skip(4)?,
+                // DOUBLE
+                4 => self.skip(8)?,
+                // STRING
+                5 => self.string()?,
+                opcode => return Err(invalid(format!("invalid RDB module opcode {}", opcode))),
+            }
+        }
+    }
+
+    fn value(&mut self, value_type: u8) -> io::Result<()> {
+        match value_type {
+            // string, and the types encoded as a single blob (ziplists, listpacks and intsets)
+            0 | 9..=13 | 16 | 17 | 20 => self.string(),
+            // list, set and quicklist
+            1 | 2 | 14 => self.repeat(Self::string),
+            // zset (with the scores as strings)
+            3 => self.repeat(|rdb| {
+                rdb.string()?;
+                rdb.legacy_double()
+            }),
+            // hash
+            4 => self.repeat(|rdb| {
+                rdb.string()?;
+                rdb.string()
+            }),
+            // zset (with binary scores)
+            5 => self.repeat(|rdb| {
+                rdb.string()?;
+                rdb.skip(8)
+            }),
+            // module: the module id followed by its data
+            7 => {
+                self.length()?;
+                self.module_value()
+            }
+            // quicklist 2: every node has a container type
+            18 => self.repeat(|rdb| {
+                rdb.length()?;
+                rdb.string()
+            }),
+            // streams
+            15 | 19 | 21 => self.stream(value_type),
+            _ => Err(invalid(format!("unsupported RDB type {}", value_type))),
+        }
+    }
+
+    fn stream(&mut self, value_type: u8) -> io::Result<()> {
+        // the listpacks, with their master ids
+        self.repeat(|rdb| {
+            rdb.string()?;
+            rdb.string()
+        })?;
+        // the length and the last id, followed by the first id, the max deleted id and the
+        // number of entries added since version 2
+        let fields = if value_type >= 19 { 7 } else { 3 };
+        for _ in 0..fields {
+            self.length()?;
+        }
+
+        // the consumer groups
+        self.repeat(|rdb| {
+            rdb.string()?;
+            rdb.length()?;
+            rdb.length()?;
+            if value_type >= 19 {
+                rdb.length()?;
+            }
+            // the pending entries: the id, the delivery time and the delivery count
+            rdb.repeat(|rdb| {
+                rdb.skip(16 + 8)?;
+                rdb.length()?;
+                Ok(())
+            })?;
+            // the consumers: the name, the seen time (and the active time since version 3)
+            // and the ids of their pending entries
+            rdb.repeat(|rdb| {
+                rdb.string()?;
+                rdb.skip(if value_type >= 21 { 16 } else { 8 })?;
+                rdb.repeat(|rdb| rdb.skip(16))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A snapshot with a few types of values, followed by some more data
+    const RDB: &[u8] = b"REDIS0011\
+        \xfa\x09redis-ver\x057.2.4\
+        \xfe\x00\xfb\x03\x01\
+        \x00\x01a\xc0\x01\
+        \xfc\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01b\xc3\x04\x06abcd\
+        \x12\x01l\x01\x02\x03abc\
+        \x04\x01h\x01\x01f\x01v\
+        \x03\x01z\x01\x01m\xfe\
+        \xff\x01\x02\x03\x04\x05\x06\x07\x08";
+
+    #[test]
+    fn test_skip() {
+        let data = [RDB, b"*1\r\n$4\r\nPING\r\n"].concat();
+        let mut reader = &data[..];
+        assert_eq!(skip(&mut reader).unwrap(), RDB.len() as u64);
+        assert_eq!(reader, b"*1\r\n$4\r\nPING\r\n");
+    }
+
+    #[test]
+    fn test_invalid() {
+        for len in 0..RDB.len() {
+            assert_eq!(
+                skip(&RDB[..len]).unwrap_err().kind(),
+                io::ErrorKind::UnexpectedEof
+            );
+        }
+        assert_eq!(
+            skip(&b"REDIS001x\xff"[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        // an unknown value type
+        assert_eq!(
+            skip(&b"REDIS0011\x63\x01a"[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
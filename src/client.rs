@@ -0,0 +1,358 @@
+//! A minimal blocking RESP client.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use tinyresp::{client::Connection, Command, OwnedValue};
+//!
+//! let mut connection = Connection::connect("127.0.0.1:6379").unwrap();
+//! let reply = connection.query(&Command::new("PING")).unwrap();
+//! assert_eq!(reply, OwnedValue::SimpleString("PONG".to_string()));
+//! ```
+//!
+//! When the `tokio` feature is enabled, this module also provides an asynchronous `Client`
+//! that supports pipelining and delivers push messages on a dedicated channel.
+
+use crate::{frame_len, Command, Decoder, OwnedValue, ParseError, Protocol, ToResp};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use thiserror::Error;
+
+#[cfg(feature = "tokio")]
+mod async_client;
+#[cfg(feature = "tokio")]
+pub use async_client::*;
+
+/// Represents an error that can occur while talking to a RESP server
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+    #[error("The connection was closed by the server")]
+    ConnectionClosed,
+    #[error("Frame larger than the limit of {0} bytes")]
+    FrameTooLarge(usize),
+}
+
+/// The default maximum size of the frames received by a client (see [Connection::with_max_frame_size])
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 512 * 1024 * 1024;
+
+impl From<ParseError> for ClientError {
+    fn from(e: ParseError) -> Self {
+        ClientError::Protocol(e.to_string())
+    }
+}
+
+/// A blocking connection to a RESP server
+pub struct Connection {
+    stream: TcpStream,
+    /// The bytes received after the last decoded value, that haven't been passed to the decoder yet
+    buffer: Vec<u8>,
+    decoder: Decoder,
+    /// The number of bytes received of the value being decoded
+    frame_size: usize,
+    max_frame_size: usize,
+    /// Set after an error that makes the stream impossible to resynchronize
+    broken: bool,
+}
+
+impl Connection {
+    /// Connects to the RESP server at the given address
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, ClientError> {
+        Ok(Connection::new(TcpStream::connect(addr)?))
+    }
+
+    /// Creates a connection from an already connected stream
+    pub fn new(stream: TcpStream) -> Self {
+        Connection {
+            stream,
+            buffer: Vec::new(),
+            decoder: Decoder::new(),
+            frame_size: 0,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            broken: false,
+        }
+    }
+
+    /// Sets the maximum size of the frames received from the server
+    /// (the default is [DEFAULT_MAX_FRAME_SIZE]).
+    ///
+    /// A larger frame fails with [ClientError::FrameTooLarge], without being buffered entirely.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Returns a reference to the underlying stream (e.g. to configure timeouts)
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Sends a command to the server without waiting for the reply.
+    /// This can be used to pipeline multiple commands and read the replies later with [Connection::recv].
+    pub fn send(&mut self, command: &Command) -> Result<(), ClientError> {
+        self.stream.write_all(&command.to_resp(Protocol::Resp2))?;
+        Ok(())
+    }
+
+    /// Receives the next value sent by the server, waiting until a complete value is available.
+    /// Error replies are returned as values ([OwnedValue::SimpleError] or [OwnedValue::BulkError]).
+    ///
+    /// A frame that can't be decoded is skipped, so the following values can still be received.
+    /// After a malformed or too large frame the end of the frame can't be found, and every
+    /// following call fails.
+    pub fn recv(&mut self) -> Result<OwnedValue, ClientError> {
+        if self.broken {
+            return Err(ClientError::Protocol(
+                "the connection can't be used after a malformed frame".to_string(),
+            ));
+        }
+        loop {
+            if !self.buffer.is_empty() {
+                // every byte is decoded once, however many reads a large frame takes
+                let decoded = match self.decoder.decode_value(&self.buffer) {
+                    Ok(decoded) => decoded,
+                    Err(e) => return Err(self.fail(e.into())),
+                };
+                self.buffer.drain(..decoded.consumed);
+                self.frame_size += decoded.consumed;
+                if self.frame_size > self.max_frame_size {
+                    return Err(self.fail(ClientError::FrameTooLarge(self.max_frame_size)));
+                }
+                if let Some(value) = decoded.value {
+                    self.frame_size = 0;
+                    return value.map_err(ClientError::from);
+                }
+            }
+
+            let mut chunk = [0u8; 4096];
+            let read = self.stream.read(&mut chunk)?;
+            if read == 0 {
+                return Err(ClientError::ConnectionClosed);
+            }
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    /// Sends a command and waits for its reply
+    pub fn query(&mut self, command: &Command) -> Result<OwnedValue, ClientError> {
+        self.send(command)?;
+        self.recv()
+    }
+
+    /// Marks the connection as unusable after an error that leaves the end of the frame unknown
+    fn fail(&mut self, error: ClientError) -> ClientError {
+        self.broken = true;
+        self.buffer.clear();
+        error
+    }
+}
+
+/// Decodes the first complete RESP frame in the given buffer, removing it from the buffer.
+/// Returns `None` if the buffer doesn't contain a complete frame yet.
+///
+/// The buffer is scanned again from its start on every call, so a frame received in many chunks
+/// is better decoded with [Decoder::decode_value], which resumes where it stopped (like [Connection] does).
+/// Bulk strings that are not valid UTF-8 are decoded as [OwnedValue::BulkBytes].
+///
+/// A frame that can't be decoded is removed from the buffer, so the following frames can still be
+/// decoded. If the end of the frame can't be found (the frame is malformed or larger than
+/// [DEFAULT_MAX_FRAME_SIZE]) the buffer is cleared, as the stream can't be resynchronized.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{client::decode_frame, OwnedValue};
+///
+/// let mut buffer = b":1\r\n$5\r\nhel".to_vec();
+/// assert_eq!(decode_frame(&mut buffer).unwrap(), Some(OwnedValue::Integer(1)));
+/// assert_eq!(decode_frame(&mut buffer).unwrap(), None);
+/// buffer.extend_from_slice(b"lo\r\n");
+/// assert_eq!(
+///     decode_frame(&mut buffer).unwrap(),
+///     Some(OwnedValue::BulkString("hello".to_string()))
+/// );
+/// assert!(buffer.is_empty());
+/// ```
+pub fn decode_frame(buffer: &mut Vec<u8>) -> Result<Option<OwnedValue>, ClientError> {
+    match frame_end(buffer, DEFAULT_MAX_FRAME_SIZE) {
+        Ok(Some(len)) => {
+            let value = decode(&buffer[..len]);
+            buffer.drain(..len);
+            value.map(Some)
+        }
+        Ok(None) => Ok(None),
+        Err(e) => {
+            buffer.clear();
+            Err(e)
+        }
+    }
+}
+
+/// Finds the end of the first frame in the buffer, checking that it's not larger than the given limit
+fn frame_end(buffer: &[u8], max_frame_size: usize) -> Result<Option<usize>, ClientError> {
+    match frame_len(buffer)? {
+        Some(len) if len > max_frame_size => Err(ClientError::FrameTooLarge(max_frame_size)),
+        None if buffer.len() > max_frame_size => Err(ClientError::FrameTooLarge(max_frame_size)),
+        len => Ok(len),
+    }
+}
+
+/// Decodes a complete frame
+fn decode(frame: &[u8]) -> Result<OwnedValue, ClientError> {
+    Decoder::new()
+        .feed(frame)?
+        .pop()
+        .ok_or_else(|| ClientError::Protocol("incomplete frame".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Starts a fake server that reads one request per reply and answers with the given reply,
+    /// writing it in small chunks to exercise the buffering of partial reads
+    fn fake_server(replies: Vec<&'static [u8]>) -> (String, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            for reply in replies {
+                let mut chunk = [0u8; 1024];
+                let read = stream.read(&mut chunk).unwrap();
+                received.extend_from_slice(&chunk[..read]);
+                for part in reply.chunks(3) {
+                    stream.write_all(part).unwrap();
+                    stream.flush().unwrap();
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            received
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_query() {
+        let (addr, server) = fake_server(vec![
+            b"+PONG\r\n",
+            b"*2\r\n$5\r\nhello\r\n%1\r\n+a\r\n:1\r\n",
+            b"-ERR unknown command\r\n",
+        ]);
+        let mut connection = Connection::connect(addr).unwrap();
+
+        assert_eq!(
+            connection.query(&Command::new("PING")).unwrap(),
+            OwnedValue::SimpleString("PONG".to_string())
+        );
+        assert_eq!(
+            connection.query(&Command::new("GET").arg("key")).unwrap(),
+            OwnedValue::Array(vec![
+                OwnedValue::BulkString("hello".to_string()),
+                OwnedValue::Map(
+                    vec![OwnedValue::SimpleString("a".to_string())],
+                    vec![OwnedValue::Integer(1)]
+                )
+            ])
+        );
+        assert_eq!(
+            connection.query(&Command::new("BOGUS")).unwrap(),
+            OwnedValue::SimpleError("ERR unknown command".to_string())
+        );
+        assert!(matches!(
+            connection.recv(),
+            Err(ClientError::ConnectionClosed)
+        ));
+
+        assert_eq!(
+            server.join().unwrap(),
+            b"*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n*1\r\n$5\r\nBOGUS\r\n"
+        );
+    }
+
+    #[test]
+    fn test_pipelined_replies() {
+        // both replies are sent in response to the first request
+        let (addr, server) = fake_server(vec![b":1\r\n:2\r\n"]);
+        let mut connection = Connection::connect(addr).unwrap();
+        connection.send(&Command::new("INCR").arg("a")).unwrap();
+        connection.send(&Command::new("INCR").arg("a")).unwrap();
+        assert_eq!(connection.recv().unwrap(), OwnedValue::Integer(1));
+        assert_eq!(connection.recv().unwrap(), OwnedValue::Integer(2));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_binary_reply() {
+        let (addr, server) = fake_server(vec![b"$4\r\n\x00\xff\r\n\r\n"]);
+        let mut connection = Connection::connect(addr).unwrap();
+        assert_eq!(
+            connection.query(&Command::new("DUMP").arg("key")).unwrap(),
+            OwnedValue::BulkBytes(b"\x00\xff\r\n".to_vec())
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_undecodable_frame_is_skipped() {
+        let (addr, server) = fake_server(vec![b"+\xff\r\n+OK\r\n"]);
+        let mut connection = Connection::connect(addr).unwrap();
+        assert!(matches!(
+            connection.query(&Command::new("PING")),
+            Err(ClientError::Protocol(_))
+        ));
+        assert_eq!(
+            connection.recv().unwrap(),
+            OwnedValue::SimpleString("OK".to_string())
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_max_frame_size() {
+        let (addr, server) = fake_server(vec![b"$10\r\n0123456789\r\n+OK\r\n"]);
+        let mut connection = Connection::connect(addr).unwrap().with_max_frame_size(8);
+        assert!(matches!(
+            connection.query(&Command::new("GET").arg("key")),
+            Err(ClientError::FrameTooLarge(8))
+        ));
+        // the end of the frame is unknown, so the connection can't be used anymore
+        assert!(matches!(connection.recv(), Err(ClientError::Protocol(_))));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_protocol_error() {
+        let (addr, server) = fake_server(vec![b"?what\r\n"]);
+        let mut connection = Connection::connect(addr).unwrap();
+        assert!(matches!(
+            connection.query(&Command::new("PING")),
+            Err(ClientError::Protocol(_))
+        ));
+        assert!(matches!(
+            connection.query(&Command::new("PING")),
+            Err(ClientError::Protocol(_))
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_decode_frame_drains_invalid_frames() {
+        let mut buffer = b"+\xff\r\n:1\r\n".to_vec();
+        assert!(decode_frame(&mut buffer).is_err());
+        assert_eq!(
+            decode_frame(&mut buffer).unwrap(),
+            Some(OwnedValue::Integer(1))
+        );
+
+        let mut buffer = b"?\r\n:1\r\n".to_vec();
+        assert!(decode_frame(&mut buffer).is_err());
+        assert!(buffer.is_empty());
+    }
+}
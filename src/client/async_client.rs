@@ -0,0 +1,540 @@
+use super::ClientError;
+use crate::{Command, Decoder, OwnedValue, Protocol, ToResp};
+use std::collections::{BTreeSet, VecDeque};
+use std::io;
+use tokio::io::{self as tokio_io, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{mpsc, oneshot};
+
+type Reply = oneshot::Sender<Result<OwnedValue, ClientError>>;
+
+struct Request {
+    bytes: Vec<u8>,
+    reply: Reply,
+    confirmation: Option<Confirmation>,
+}
+
+/// The push messages confirming a (un)subscribe command, one for each of its channels or patterns
+struct Confirmation {
+    /// The kind of the push messages (the name of the command in lowercase)
+    kind: String,
+    /// The number of confirmations still expected, unknown for the unsubscribe commands without
+    /// arguments until the confirmations of the previous commands are received
+    remaining: Option<usize>,
+}
+
+/// A request written to the server and waiting for its reply
+struct Pending {
+    /// Taken once the reply is sent, while the other confirmations of the request are expected
+    reply: Option<Reply>,
+    confirmation: Option<Confirmation>,
+}
+
+/// The commands that are confirmed by a push message over RESP3
+const SUBSCRIBE_COMMANDS: [&str; 6] = [
+    "subscribe",
+    "psubscribe",
+    "ssubscribe",
+    "unsubscribe",
+    "punsubscribe",
+    "sunsubscribe",
+];
+
+/// An asynchronous RESP client (requires the `tokio` feature).
+///
+/// All the requests are multiplexed over a single connection: they are written as soon as they
+/// are submitted (without waiting for the previous replies) and the replies are matched to the
+/// requests in FIFO order. The client can be cloned to submit requests from multiple tasks.
+///
+/// Out-of-band push messages ([OwnedValue::Pushes], e.g. Pub/Sub messages or client side
+/// caching invalidations received over RESP3) are never matched to a request, they are delivered
+/// to the [PushReceiver] returned when the client is created. The only exception are the
+/// confirmations of the (un)subscribe commands, which are push messages over RESP3: the first
+/// confirmation is the reply to the command, while the confirmations of its other channels are
+/// delivered to the [PushReceiver] like any other push message (they're still counted, so that
+/// the following replies are matched to the right requests).
+///
+/// A reply that can't be decoded (e.g. a simple string that is not valid UTF-8) fails the request
+/// it's matched to, like with [Connection](super::Connection). After a malformed frame the stream
+/// can't be resynchronized, and the connection is closed.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> Result<(), tinyresp::client::ClientError> {
+/// use tinyresp::{client::Client, Command};
+///
+/// let (client, mut pushes) = Client::connect("127.0.0.1:6379").await?;
+/// let incr_a = Command::new("INCR").arg("a");
+/// let incr_b = Command::new("INCR").arg("b");
+/// // both commands are written before the first reply is received
+/// let (a, b) = tokio::join!(client.query(&incr_a), client.query(&incr_b));
+///
+/// client.query(&Command::new("SUBSCRIBE").arg("news")).await?;
+/// while let Some(push) = pushes.recv().await {
+///     println!("{:?}", push.as_value().as_push());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+/// The receiving side of the channel where a [Client] delivers push messages
+pub type PushReceiver = mpsc::UnboundedReceiver<OwnedValue>;
+
+impl Client {
+    /// Connects to the RESP server at the given address
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<(Self, PushReceiver), ClientError> {
+        Ok(Client::new(TcpStream::connect(addr).await?))
+    }
+
+    /// Creates a client from an already connected stream.
+    /// This spawns a task (on the current tokio runtime) that drives the connection,
+    /// the task terminates when every clone of the client has been dropped or the connection is closed.
+    pub fn new<S>(stream: S) -> (Self, PushReceiver)
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let (requests, requests_rx) = mpsc::unbounded_channel();
+        let (pushes, pushes_rx) = mpsc::unbounded_channel();
+        tokio::spawn(drive(stream, requests_rx, pushes));
+        (Client { requests }, pushes_rx)
+    }
+
+    /// Sends a command and waits for its reply.
+    /// Error replies are returned as values ([OwnedValue::SimpleError] or [OwnedValue::BulkError]).
+    pub async fn query(&self, command: &Command) -> Result<OwnedValue, ClientError> {
+        let (reply, reply_rx) = oneshot::channel();
+        let args = command.parts().len() - 1;
+        let confirmation = command
+            .name()
+            .map(str::to_ascii_lowercase)
+            .filter(|name| SUBSCRIBE_COMMANDS.contains(&name.as_str()))
+            .map(|kind| Confirmation {
+                remaining: match args {
+                    0 if kind.contains("unsubscribe") => None,
+                    // without arguments, the subscribe commands are answered with an error
+                    args => Some(args.max(1)),
+                },
+                kind,
+            });
+        self.requests
+            .send(Request {
+                bytes: command.to_resp(Protocol::Resp2),
+                reply,
+                confirmation,
+            })
+            .map_err(|_| ClientError::ConnectionClosed)?;
+        reply_rx.await.map_err(|_| ClientError::ConnectionClosed)?
+    }
+}
+
+/// Drives the connection: the requests are written while the replies are read, so that a server
+/// blocked writing the replies to a large pipeline never stops the requests from being written
+async fn drive<S>(
+    stream: S,
+    mut requests: mpsc::UnboundedReceiver<Request>,
+    pushes: mpsc::UnboundedSender<OwnedValue>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut reader, mut writer) = tokio_io::split(stream);
+    let mut pending: VecDeque<Pending> = VecDeque::new();
+    // the bytes received after the last decoded value, that haven't been passed to the decoder yet
+    let mut buffer = Vec::new();
+    let mut decoder = Decoder::new();
+    // the channels and patterns the connection is subscribed to, with the kind of their subscription
+    let mut subscriptions = BTreeSet::new();
+    // the requests that have been submitted but not written yet
+    let mut outgoing = Vec::new();
+    let mut accepting = true;
+
+    let error = 'connection: loop {
+        tokio::select! {
+            request = requests.recv(), if accepting => match request {
+                Some(request) => {
+                    outgoing.extend_from_slice(&request.bytes);
+                    pending.push_back(Pending {
+                        reply: Some(request.reply),
+                        confirmation: request.confirmation,
+                    });
+                }
+                // every client has been dropped, wait for the pending replies
+                None => accepting = false,
+            },
+            written = writer.write(&outgoing), if !outgoing.is_empty() => match written {
+                Ok(0) => break ClientError::ConnectionClosed,
+                Ok(written) => {
+                    outgoing.drain(..written);
+                }
+                Err(e) => break closed_or_io(e),
+            },
+            read = reader.read_buf(&mut buffer) => match read {
+                Ok(0) => break ClientError::ConnectionClosed,
+                Ok(_) => while !buffer.is_empty() {
+                    let value = match decoder.decode_value(&buffer) {
+                        Ok(decoded) => {
+                            buffer.drain(..decoded.consumed);
+                            match decoded.value {
+                                Some(value) => value,
+                                None => break,
+                            }
+                        }
+                        // the stream can't be resynchronized after a malformed frame
+                        Err(e) => break 'connection ClientError::from(e),
+                    };
+                    match value {
+                        Ok(OwnedValue::Pushes(values)) => {
+                            confirm(&mut pending, &mut subscriptions, &pushes, values)
+                        }
+                        // a value that can't be decoded is skipped, failing the request it replies to
+                        value => {
+                            // the requests still expecting confirmations can't be answered anymore
+                            while pending.front().is_some_and(|request| request.reply.is_none()) {
+                                pending.pop_front();
+                            }
+                            if let Some(reply) = pending.pop_front().and_then(|request| request.reply) {
+                                let _ = reply.send(value.map_err(ClientError::from));
+                            }
+                        }
+                    }
+                },
+                Err(e) => break closed_or_io(e),
+            },
+        }
+
+        if !accepting && pending.is_empty() {
+            return;
+        }
+    };
+
+    if let Some(reply) = pending.into_iter().find_map(|request| request.reply) {
+        let _ = reply.send(Err(error));
+    }
+}
+
+/// Handles a push message: over RESP3 the confirmations of the (un)subscribe commands are push
+/// messages, the first one is the reply to the command and the others are delivered as pushes
+fn confirm(
+    pending: &mut VecDeque<Pending>,
+    subscriptions: &mut BTreeSet<(String, OwnedValue)>,
+    pushes: &mpsc::UnboundedSender<OwnedValue>,
+    values: Vec<OwnedValue>,
+) {
+    let kind = values
+        .first()
+        .and_then(|kind| Some(kind.as_value().as_str()?.to_ascii_lowercase()));
+    let Some(kind) = kind.filter(|kind| SUBSCRIBE_COMMANDS.contains(&kind.as_str())) else {
+        let _ = pushes.send(OwnedValue::Pushes(values));
+        return;
+    };
+    // the unsubscribe commands share the kind of subscription of the subscribe commands
+    let subscription = kind.replacen("un", "", 1);
+
+    let mut reply = None;
+    if let Some(request) = pending.front_mut() {
+        if let Some(confirmation) = request
+            .confirmation
+            .as_mut()
+            .filter(|confirmation| confirmation.kind == kind)
+        {
+            // an unsubscribe command without arguments is confirmed once for every subscription of
+            // its kind (or once if there are none), which are all known once its turn has come
+            let remaining = confirmation.remaining.get_or_insert_with(|| {
+                let count = subscriptions
+                    .iter()
+                    .filter(|(kind, _)| *kind == subscription)
+                    .count();
+                count.max(1)
+            });
+            *remaining -= 1;
+            reply = request.reply.take();
+            if *remaining == 0 {
+                pending.pop_front();
+            }
+        }
+    }
+
+    if let Some(channel) = values.get(1) {
+        let entry = (subscription, channel.clone());
+        if kind.contains("unsubscribe") {
+            subscriptions.remove(&entry);
+        } else {
+            subscriptions.insert(entry);
+        }
+    }
+    let push = OwnedValue::Pushes(values);
+    match reply {
+        Some(reply) => {
+            let _ = reply.send(Ok(push));
+        }
+        None => {
+            let _ = pushes.send(push);
+        }
+    }
+}
+
+/// Reports the errors caused by the server closing the connection as [ClientError::ConnectionClosed],
+/// whether they're noticed while writing a request or while reading a reply
+fn closed_or_io(e: io::Error) -> ClientError {
+    match e.kind() {
+        io::ErrorKind::BrokenPipe
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::UnexpectedEof => ClientError::ConnectionClosed,
+        _ => ClientError::Io(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    #[tokio::test]
+    async fn test_pipelining() {
+        let (client_side, mut server_side) = duplex(1024);
+        let (client, mut pushes) = Client::new(client_side);
+
+        let server = tokio::spawn(async move {
+            let expected: &[u8] = b"*2\r\n$4\r\nINCR\r\n$1\r\na\r\n*2\r\n$4\r\nINCR\r\n$1\r\nb\r\n";
+            let mut received = vec![0u8; expected.len()];
+            server_side.read_exact(&mut received).await.unwrap();
+            assert_eq!(received, expected);
+            // a push message is interleaved with the replies, which are split in partial writes
+            for part in [
+                &b":1\r\n>3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n"[..],
+                &b"$5\r\nhello\r\n:"[..],
+                &b"2\r\n"[..],
+            ] {
+                server_side.write_all(part).await.unwrap();
+                server_side.flush().await.unwrap();
+                tokio::task::yield_now().await;
+            }
+            server_side
+        });
+
+        let incr_a = Command::new("INCR").arg("a");
+        let incr_b = Command::new("INCR").arg("b");
+        let (a, b) = tokio::join!(client.query(&incr_a), client.query(&incr_b));
+        assert_eq!(a.unwrap(), OwnedValue::Integer(1));
+        assert_eq!(b.unwrap(), OwnedValue::Integer(2));
+        assert_eq!(
+            pushes.recv().await.unwrap(),
+            OwnedValue::Pushes(vec![
+                OwnedValue::BulkString("message".to_string()),
+                OwnedValue::BulkString("news".to_string()),
+                OwnedValue::BulkString("hello".to_string()),
+            ])
+        );
+
+        // closing the connection fails the pending requests
+        drop(server.await.unwrap());
+        assert!(matches!(
+            client.query(&Command::new("PING")).await,
+            Err(ClientError::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_confirmations() {
+        let (client_side, mut server_side) = duplex(1024);
+        let (client, mut pushes) = Client::new(client_side);
+
+        let server = tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let _ = server_side.read(&mut request).await.unwrap();
+            // one confirmation for each channel, then the reply to the following command
+            server_side
+                .write_all(b">3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n")
+                .await
+                .unwrap();
+            server_side
+                .write_all(b">3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n+PONG\r\n")
+                .await
+                .unwrap();
+            server_side
+        });
+
+        let subscribe = Command::new("SUBSCRIBE").args(["a", "b"]);
+        let ping = Command::new("PING");
+        let (subscribed, pong) = tokio::join!(client.query(&subscribe), client.query(&ping));
+        assert_eq!(
+            subscribed.unwrap(),
+            OwnedValue::Pushes(vec![
+                OwnedValue::BulkString("subscribe".to_string()),
+                OwnedValue::BulkString("a".to_string()),
+                OwnedValue::Integer(1),
+            ])
+        );
+        assert_eq!(pong.unwrap(), OwnedValue::SimpleString("PONG".to_string()));
+        assert_eq!(
+            pushes.recv().await.unwrap(),
+            OwnedValue::Pushes(vec![
+                OwnedValue::BulkString("subscribe".to_string()),
+                OwnedValue::BulkString("b".to_string()),
+                OwnedValue::Integer(2),
+            ])
+        );
+        drop(server.await.unwrap());
+    }
+
+    fn confirmation(kind: &str, channel: &str, count: i64) -> OwnedValue {
+        OwnedValue::Pushes(vec![
+            OwnedValue::BulkString(kind.to_string()),
+            OwnedValue::BulkString(channel.to_string()),
+            OwnedValue::Integer(count),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_subscribe_confirmations() {
+        let (client_side, mut server_side) = duplex(1024);
+        let (client, mut pushes) = Client::new(client_side);
+
+        let server = tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let mut received = 0;
+            // SUBSCRIBE a b, SUBSCRIBE c, UNSUBSCRIBE and PING
+            while received < 44 + 30 + 25 + 14 {
+                received += server_side.read(&mut request).await.unwrap();
+            }
+            for confirmation in [
+                &b">3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n"[..],
+                b">3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n",
+                b">3\r\n$9\r\nsubscribe\r\n$1\r\nc\r\n:3\r\n",
+                b">3\r\n$11\r\nunsubscribe\r\n$1\r\nb\r\n:2\r\n",
+                b">3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n",
+                b">3\r\n$11\r\nunsubscribe\r\n$1\r\nc\r\n:0\r\n",
+                b"+PONG\r\n",
+            ] {
+                server_side.write_all(confirmation).await.unwrap();
+            }
+            server_side
+        });
+
+        let subscribe_ab = Command::new("SUBSCRIBE").args(["a", "b"]);
+        let subscribe_c = Command::new("SUBSCRIBE").arg("c");
+        let unsubscribe = Command::new("UNSUBSCRIBE");
+        let ping = Command::new("PING");
+        let (ab, c, unsubscribed, pong) = tokio::join!(
+            client.query(&subscribe_ab),
+            client.query(&subscribe_c),
+            client.query(&unsubscribe),
+            client.query(&ping)
+        );
+        assert_eq!(ab.unwrap(), confirmation("subscribe", "a", 1));
+        assert_eq!(c.unwrap(), confirmation("subscribe", "c", 3));
+        // unsubscribing from every channel is confirmed once per channel
+        assert_eq!(unsubscribed.unwrap(), confirmation("unsubscribe", "b", 2));
+        assert_eq!(pong.unwrap(), OwnedValue::SimpleString("PONG".to_string()));
+        for expected in [
+            confirmation("subscribe", "b", 2),
+            confirmation("unsubscribe", "a", 1),
+            confirmation("unsubscribe", "c", 0),
+        ] {
+            assert_eq!(pushes.recv().await.unwrap(), expected);
+        }
+        drop(server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_reply_is_skipped() {
+        let (client_side, mut server_side) = duplex(1024);
+        let (client, _pushes) = Client::new(client_side);
+        let server = tokio::spawn(async move {
+            let mut request = vec![0u8; 28];
+            server_side.read_exact(&mut request).await.unwrap();
+            server_side.write_all(b"+\xff\r\n+PONG\r\n").await.unwrap();
+            server_side
+        });
+
+        let ping = Command::new("PING");
+        let (first, second) = tokio::join!(client.query(&ping), client.query(&ping));
+        assert!(matches!(first, Err(ClientError::Protocol(_))));
+        assert_eq!(
+            second.unwrap(),
+            OwnedValue::SimpleString("PONG".to_string())
+        );
+        drop(server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_large_pipeline() {
+        // the server doesn't read the second request until the reply to the first one is sent,
+        // which requires the client to read while it's still writing
+        let (client_side, mut server_side) = duplex(64);
+        let (client, _pushes) = Client::new(client_side);
+        let value = "x".repeat(4096);
+
+        let server = {
+            let value = value.clone();
+            tokio::spawn(async move {
+                let mut request = vec![0u8; 20];
+                server_side.read_exact(&mut request).await.unwrap();
+                assert_eq!(request, b"*2\r\n$3\r\nGET\r\n$1\r\na\r\n");
+                let reply = format!("${}\r\n{}\r\n", value.len(), value);
+                server_side.write_all(reply.as_bytes()).await.unwrap();
+                let mut request = vec![0u8; 4096 + 29];
+                server_side.read_exact(&mut request).await.unwrap();
+                server_side.write_all(b"+OK\r\n").await.unwrap();
+                server_side
+            })
+        };
+
+        let get = Command::new("GET").arg("a");
+        let set = Command::new("SET").arg("b").arg(&value);
+        let query = async { tokio::join!(client.query(&get), client.query(&set)) };
+        let (got, set) = tokio::time::timeout(std::time::Duration::from_secs(10), query)
+            .await
+            .expect("the client is deadlocked");
+        assert_eq!(got.unwrap(), OwnedValue::BulkString(value));
+        assert_eq!(set.unwrap(), OwnedValue::SimpleString("OK".to_string()));
+        drop(server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 14];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, b"*1\r\n$4\r\nPING\r\n");
+            stream.write_all(b"+PONG\r\n").await.unwrap();
+        });
+
+        let (client, _pushes) = Client::connect(addr).await.unwrap();
+        assert_eq!(
+            client.query(&Command::new("PING")).await.unwrap(),
+            OwnedValue::SimpleString("PONG".to_string())
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_protocol_error() {
+        let (client_side, mut server_side) = duplex(1024);
+        let (client, _pushes) = Client::new(client_side);
+        let server = tokio::spawn(async move {
+            let mut request = vec![0u8; 14];
+            server_side.read_exact(&mut request).await.unwrap();
+            server_side.write_all(b"?what\r\n").await.unwrap();
+            server_side
+        });
+
+        assert!(matches!(
+            client.query(&Command::new("PING")).await,
+            Err(ClientError::Protocol(_))
+        ));
+        let _server_side = server.await.unwrap();
+        assert!(matches!(
+            client.query(&Command::new("PING")).await,
+            Err(ClientError::ConnectionClosed)
+        ));
+    }
+}
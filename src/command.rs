@@ -0,0 +1,95 @@
+use crate::to_resp::{write_bulk, write_header};
+use crate::{Protocol, ToResp};
+
+/// Represents a command to be sent to a RESP server.
+///
+/// Commands are always encoded as an array of bulk strings (a "multibulk"),
+/// which is the format every RESP server understands.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{Command, Protocol, ToResp};
+///
+/// let command = Command::new("SET").arg("key").arg(42.to_string());
+/// assert_eq!(command.name(), Some("SET"));
+/// assert_eq!(
+///     command.to_resp(Protocol::Resp3),
+///     b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$2\r\n42\r\n"
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Command {
+    args: Vec<Vec<u8>>,
+}
+
+impl Command {
+    /// Creates a new command with the given name
+    pub fn new(name: impl AsRef<[u8]>) -> Self {
+        Command {
+            args: vec![name.as_ref().to_vec()],
+        }
+    }
+
+    /// Adds an argument to the command
+    pub fn arg(mut self, arg: impl AsRef<[u8]>) -> Self {
+        self.args.push(arg.as_ref().to_vec());
+        self
+    }
+
+    /// Adds multiple arguments to the command
+    pub fn args<I, T>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_vec()));
+        self
+    }
+
+    /// Returns the name of the command (if it's valid UTF-8)
+    pub fn name(&self) -> Option<&str> {
+        std::str::from_utf8(&self.args[0]).ok()
+    }
+
+    /// Returns all the parts of the command, including the name
+    pub fn parts(&self) -> &[Vec<u8>] {
+        &self.args
+    }
+}
+
+impl ToResp for Command {
+    fn write_resp(&self, out: &mut Vec<u8>, _protocol: Protocol) {
+        write_header(out, b'*', self.args.len());
+        for arg in &self.args {
+            write_bulk(out, b'$', arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let command = Command::new("MSET").args(["a", "1"]).arg(b"b").arg(b"\xff");
+        assert_eq!(command.name(), Some("MSET"));
+        assert_eq!(
+            command.parts(),
+            &[
+                b"MSET".to_vec(),
+                b"a".to_vec(),
+                b"1".to_vec(),
+                b"b".to_vec(),
+                b"\xff".to_vec()
+            ]
+        );
+        assert_eq!(
+            command.to_resp(Protocol::Resp2),
+            b"*5\r\n$4\r\nMSET\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n\xff\r\n"
+        );
+        assert_eq!(Command::new(b"\xff").name(), None);
+    }
+}
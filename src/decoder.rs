@@ -0,0 +1,806 @@
+use crate::{parse_length, parse_value, Length, OwnedValue, ParseError, VerbatimFormat, MAX_DEPTH};
+use std::io::{self, Write};
+
+/// An aggregate whose values are being decoded
+#[derive(Debug)]
+struct Aggregate {
+    prefix: u8,
+    /// The number of values still to be decoded (keys and values are counted separately for maps)
+    remaining: usize,
+    values: Vec<OwnedValue>,
+}
+
+impl Aggregate {
+    fn into_value(self) -> OwnedValue {
+        match self.prefix {
+            b'*' => OwnedValue::Array(self.values),
+            b'~' => OwnedValue::Set(self.values.into_iter().collect()),
+            b'>' => OwnedValue::Pushes(self.values),
+            _ => {
+                let (keys, values): (Vec<_>, Vec<_>) = self
+                    .values
+                    .into_iter()
+                    .enumerate()
+                    .partition(|(idx, _)| idx % 2 == 0);
+                OwnedValue::Map(
+                    keys.into_iter().map(|(_, key)| key).collect(),
+                    values.into_iter().map(|(_, value)| value).collect(),
+                )
+            }
+        }
+    }
+}
+
+/// A bulk string, bulk error or verbatim string whose content is being received
+#[derive(Debug)]
+struct Bulk {
+    prefix: u8,
+    /// The position of the content in the stream
+    offset: usize,
+    /// The length of the content, excluding the final CRLF
+    len: usize,
+    /// The number of bytes received so far, including the final CRLF
+    received: usize,
+    /// The bytes received so far, without the content if it's streamed to the sink
+    data: Vec<u8>,
+    /// The position of the content in the sink, if it's streamed to it
+    streamed: Option<u64>,
+}
+
+/// The placeholder of a bulk string streamed to the sink of a [Decoder] (see [Decoder::with_sink])
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct StreamedBulk {
+    /// The position of the content in the sink (i.e. the number of bytes written to it before)
+    pub offset: u64,
+    /// The length of the content
+    pub len: usize,
+}
+
+impl Bulk {
+    fn into_value(mut self) -> Result<OwnedValue, ParseError> {
+        let content_len = self.data.len() - 2;
+        if &self.data[content_len..] != b"\r\n" {
+            return Err(ParseError::ExpectedCrlf {
+                offset: self.offset + self.len,
+            });
+        }
+        if let Some(offset) = self.streamed {
+            return Ok(OwnedValue::Streamed(StreamedBulk {
+                offset,
+                len: self.len,
+            }));
+        }
+        self.data.truncate(content_len);
+        let format = if self.prefix == b'=' {
+            let format = std::str::from_utf8(&self.data[..3])
+                .ok()
+                .and_then(VerbatimFormat::from_prefix)
+                .ok_or(ParseError::InvalidValue {
+                    offset: self.offset,
+                })?;
+            if self.data[3] != b':' {
+                return Err(ParseError::InvalidValue {
+                    offset: self.offset + 3,
+                });
+            }
+            self.data.drain(..4);
+            Some(format)
+        } else {
+            None
+        };
+        let content = match String::from_utf8(self.data) {
+            Ok(content) => content,
+            // bulk strings can contain any bytes
+            Err(e) if self.prefix == b'$' => return Ok(OwnedValue::BulkBytes(e.into_bytes())),
+            Err(_) => {
+                return Err(ParseError::InvalidValue {
+                    offset: self.offset,
+                })
+            }
+        };
+        Ok(match (self.prefix, format) {
+            (b'$', _) => OwnedValue::BulkString(content),
+            (b'!', _) => OwnedValue::BulkError(content),
+            (_, Some(format)) => OwnedValue::VerbatimString(format, content),
+            _ => unreachable!("bulks are only created for `$`, `!` and `=`"),
+        })
+    }
+}
+
+/// A value decoded by [Decoder::decode_value]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Decoded {
+    /// The number of bytes consumed
+    pub consumed: usize,
+    /// The value, if it's complete, or the error that made it invalid
+    pub value: Option<Result<OwnedValue, ParseError>>,
+}
+
+/// Decodes RESP values from a stream received in chunks of arbitrary size.
+///
+/// Unlike [parse_value], which must be called again on the whole buffer until a frame is complete,
+/// the decoder remembers where it stopped inside a frame (e.g. in the middle of a large bulk string,
+/// or after the first elements of an array) and resumes from there when more bytes are fed,
+/// so every byte is decoded only once whatever the size of the chunks.
+///
+/// The values are decoded like [parse_value] does with the default options, except for the bulk strings
+/// that are not valid UTF-8, which are decoded as [OwnedValue::BulkBytes].
+/// After an error the stream can't be resynchronized: the values completed by the same chunk are discarded
+/// and the decoder keeps returning the same error. [Decoder::decode_value] can be used instead to
+/// skip the values that are invalid but whose end is known (e.g. a simple string that is not valid UTF-8).
+///
+/// Bulk strings larger than a threshold can be written to a sink as they're received instead of being
+/// kept in memory (see [Decoder::with_sink]).
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{Decoder, OwnedValue};
+///
+/// let mut decoder = Decoder::new();
+/// assert_eq!(decoder.feed(b"*2\r\n$5\r\nhel"), Ok(vec![]));
+/// assert!(decoder.in_frame());
+/// assert_eq!(
+///     decoder.feed(b"lo\r\n:1\r\n+OK\r\n"),
+///     Ok(vec![
+///         OwnedValue::Array(vec![
+///             OwnedValue::BulkString("hello".to_string()),
+///             OwnedValue::Integer(1),
+///         ]),
+///         OwnedValue::SimpleString("OK".to_string()),
+///     ])
+/// );
+/// assert!(!decoder.in_frame());
+/// ```
+#[derive(Debug)]
+pub struct Decoder<W = io::Sink> {
+    /// The bytes received but not decoded yet (the content of bulk strings is moved out of it)
+    buffer: Vec<u8>,
+    /// The position in `buffer` before which no CRLF can start, to avoid scanning long lines again
+    scanned: usize,
+    /// The position of the start of `buffer` in the stream (used for the error offsets)
+    offset: usize,
+    bulk: Option<Bulk>,
+    /// The aggregates being decoded, from the outermost one
+    stack: Vec<Aggregate>,
+    error: Option<ParseError>,
+    /// The first error found in the value being decoded by [Decoder::decode_value],
+    /// which is returned once the value is complete
+    invalid: Option<ParseError>,
+    sink: W,
+    /// The length above which bulk strings are streamed to the sink
+    threshold: usize,
+    /// The number of bytes written to the sink
+    written: u64,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::with_sink(usize::MAX, io::sink())
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+impl<W: Write> Decoder<W> {
+    /// Creates a decoder writing the content of the bulk strings longer than `threshold` to the
+    /// given sink as it's received, so that large payloads (e.g. the reply to a `GET` of a huge value)
+    /// don't have to fit in memory.
+    ///
+    /// The other values are decoded normally, with a [OwnedValue::Streamed] placeholder giving the
+    /// position of each streamed bulk string in the sink. The streamed content is not validated
+    /// (e.g. it may not be valid UTF-8). Bulk errors and verbatim strings are never streamed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::{Decoder, OwnedValue, StreamedBulk};
+    ///
+    /// let mut decoder = Decoder::with_sink(4, Vec::new());
+    /// let values = decoder.feed(b"*3\r\n$5\r\nfirst\r\n$2\r\nOK\r\n$6\r\nsecond\r\n").unwrap();
+    /// assert_eq!(
+    ///     values,
+    ///     vec![OwnedValue::Array(vec![
+    ///         OwnedValue::Streamed(StreamedBulk { offset: 0, len: 5 }),
+    ///         OwnedValue::BulkString("OK".to_string()),
+    ///         OwnedValue::Streamed(StreamedBulk { offset: 5, len: 6 }),
+    ///     ])]
+    /// );
+    /// assert_eq!(decoder.sink(), b"firstsecond");
+    /// ```
+    pub fn with_sink(threshold: usize, sink: W) -> Self {
+        Decoder {
+            buffer: Vec::new(),
+            scanned: 0,
+            offset: 0,
+            bulk: None,
+            stack: Vec::new(),
+            error: None,
+            invalid: None,
+            sink,
+            threshold,
+            written: 0,
+        }
+    }
+
+    /// Returns a reference to the sink
+    pub fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    /// Returns a mutable reference to the sink
+    pub fn sink_mut(&mut self) -> &mut W {
+        &mut self.sink
+    }
+
+    /// Consumes the decoder, returning the sink
+    pub fn into_sink(self) -> W {
+        self.sink
+    }
+
+    /// Decodes the given chunk of the stream, returning the values completed by it.
+    ///
+    /// The offsets of the errors are relative to the start of the stream.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<OwnedValue>, ParseError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.buffer.extend_from_slice(chunk);
+        let mut values = Vec::new();
+        let result = self.decode(&mut values, false);
+        if let Err(error) = &result {
+            self.error = Some(error.clone());
+        }
+        // the values are never invalid when they're not decoded one at a time
+        result.and_then(|_| values.into_iter().collect())
+    }
+
+    /// Decodes the beginning of the given bytes until a value is complete, returning the number
+    /// of bytes consumed and the value (or `None` if all the bytes were consumed without completing it).
+    ///
+    /// Unlike [Decoder::feed], the bytes after the value are not consumed: they must be passed again
+    /// to decode the next value. This allows the caller to handle the data between the values itself
+    /// (e.g. inline commands) and to limit the size of a value while it's received.
+    ///
+    /// A value that is invalid but whose end is known (e.g. a simple string that is not valid UTF-8)
+    /// is consumed entirely and returned as an error, and the decoder can still be used for the next values.
+    /// The errors that make the end of the value impossible to find are returned as the outer error,
+    /// after which the decoder can't be used anymore.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::{Decoded, Decoder, OwnedValue, ParseError};
+    ///
+    /// let mut decoder = Decoder::new();
+    /// let mut buffer = b"+OK\r\n:1".to_vec();
+    /// let decoded = decoder.decode_value(&buffer).unwrap();
+    /// assert_eq!(
+    ///     decoded,
+    ///     Decoded { consumed: 5, value: Some(Ok(OwnedValue::SimpleString("OK".to_string()))) }
+    /// );
+    /// buffer.drain(..decoded.consumed);
+    /// assert_eq!(decoder.decode_value(&buffer), Ok(Decoded { consumed: 2, value: None }));
+    ///
+    /// // the rest of the integer is received with an invalid value, which can be skipped
+    /// let decoded = decoder.decode_value(b"x\r\n+\xff\r\n+OK\r\n").unwrap();
+    /// assert_eq!(decoded.consumed, 3);
+    /// assert_eq!(decoded.value, Some(Err(ParseError::InvalidValue { offset: 6 })));
+    /// ```
+    pub fn decode_value(&mut self, bytes: &[u8]) -> Result<Decoded, ParseError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.buffer.extend_from_slice(bytes);
+        let mut values = Vec::new();
+        if let Err(error) = self.decode(&mut values, true) {
+            self.error = Some(error.clone());
+            return Err(error);
+        }
+        let Some(value) = values.pop() else {
+            // the beginning of an incomplete line is kept in the buffer
+            return Ok(Decoded {
+                consumed: bytes.len(),
+                value: None,
+            });
+        };
+        // the bytes left after a complete value are the beginning of the next one, which are all
+        // part of the given bytes since the previous ones were needed to complete the value
+        let unconsumed = self.buffer.len();
+        self.buffer.clear();
+        self.scanned = 0;
+        Ok(Decoded {
+            consumed: bytes.len() - unconsumed,
+            value: Some(value),
+        })
+    }
+
+    /// Returns `true` if a value has been partially received
+    pub fn in_frame(&self) -> bool {
+        !self.buffer.is_empty() || self.bulk.is_some() || !self.stack.is_empty()
+    }
+
+    /// Decodes the buffer, stopping after the first complete value if `one` is set
+    /// (in which case the invalid values whose end is known are returned instead of failing)
+    fn decode(
+        &mut self,
+        values: &mut Vec<Result<OwnedValue, ParseError>>,
+        one: bool,
+    ) -> Result<(), ParseError> {
+        let mut start = 0;
+        let result = loop {
+            if one && !values.is_empty() {
+                break Ok(());
+            }
+            if let Some(bulk) = &mut self.bulk {
+                let needed = bulk.len + 2 - bulk.received;
+                let available = needed.min(self.buffer.len() - start);
+                let mut bytes = &self.buffer[start..start + available];
+                if bulk.streamed.is_some() {
+                    // the final CRLF is kept to be validated
+                    let content = bulk.len.saturating_sub(bulk.received).min(available);
+                    if let Err(error) = self.sink.write_all(&bytes[..content]) {
+                        break Err(ParseError::Sink {
+                            offset: self.offset + start,
+                            kind: error.kind(),
+                        });
+                    }
+                    self.written += content as u64;
+                    bytes = &bytes[content..];
+                }
+                bulk.data.extend_from_slice(bytes);
+                bulk.received += available;
+                start += available;
+                if available < needed {
+                    break Ok(());
+                }
+                match self.bulk.take().unwrap().into_value() {
+                    Ok(value) => self.complete(value, values),
+                    // the CRLF was checked, so the end of the value is known
+                    Err(error @ ParseError::InvalidValue { .. }) if one => {
+                        self.reject(error, values)
+                    }
+                    Err(error) => break Err(error),
+                }
+                continue;
+            }
+            match self.decode_line(start, values, one) {
+                Ok(Some(end)) => start = end,
+                Ok(None) => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+        // only the beginning of a line can be left in the buffer
+        self.buffer.drain(..start);
+        self.scanned = self.scanned.saturating_sub(start);
+        self.offset += start;
+        result
+    }
+
+    /// Decodes the line starting at `start` in the buffer, returning the position after it
+    /// or `None` if the line is not complete yet
+    fn decode_line(
+        &mut self,
+        start: usize,
+        values: &mut Vec<Result<OwnedValue, ParseError>>,
+        one: bool,
+    ) -> Result<Option<usize>, ParseError> {
+        let Some(&prefix) = self.buffer.get(start) else {
+            return Ok(None);
+        };
+        if !b"+-:$*_#,(!=%~>".contains(&prefix) {
+            return Err(ParseError::InvalidType {
+                offset: self.offset + start,
+                byte: prefix as char,
+            });
+        }
+        // a CR at the end of the scanned bytes may be followed by a LF in the next chunk
+        let from = self.scanned.saturating_sub(1).max(start + 1);
+        let Some(line_end) =
+            memchr::memmem::find(&self.buffer[from..], b"\r\n").map(|pos| from + pos)
+        else {
+            self.scanned = self.buffer.len();
+            return Ok(None);
+        };
+        self.scanned = 0;
+        let line = &self.buffer[start + 1..line_end];
+        let offset = self.offset + start + 1;
+        let header_end = line_end + 2;
+
+        match prefix {
+            b'$' | b'!' | b'=' => match parse_length(line, prefix == b'$', false) {
+                Some(Length::Null) => self.complete(OwnedValue::Null, values),
+                Some(Length::Count(len)) if prefix != b'=' || len >= 4 => {
+                    let streamed = (prefix == b'$' && len > self.threshold).then_some(self.written);
+                    let capacity = if streamed.is_some() {
+                        2
+                    } else {
+                        len.saturating_add(2)
+                    };
+                    self.bulk = Some(Bulk {
+                        prefix,
+                        offset: self.offset + header_end,
+                        len,
+                        received: 0,
+                        // the length is not trusted until the content is received
+                        data: Vec::with_capacity(capacity.min(64 * 1024)),
+                        streamed,
+                    });
+                }
+                _ => return Err(ParseError::InvalidLength { offset }),
+            },
+            b'*' | b'%' | b'~' | b'>' => {
+                if self.stack.len() >= MAX_DEPTH {
+                    return Err(ParseError::TooDeep {
+                        offset: self.offset + start,
+                    });
+                }
+                let count = match parse_length(line, prefix == b'*', false) {
+                    Some(Length::Null) => None,
+                    Some(Length::Count(len)) if prefix == b'%' => Some(
+                        len.checked_mul(2)
+                            .ok_or(ParseError::InvalidLength { offset })?,
+                    ),
+                    Some(Length::Count(len)) => Some(len),
+                    None => return Err(ParseError::InvalidLength { offset }),
+                };
+                let aggregate = Aggregate {
+                    prefix,
+                    remaining: count.unwrap_or(0),
+                    values: Vec::with_capacity(count.unwrap_or(0).min(1024)),
+                };
+                match count {
+                    None => self.complete(OwnedValue::Null, values),
+                    Some(0) => self.complete(aggregate.into_value(), values),
+                    Some(_) => self.stack.push(aggregate),
+                }
+            }
+            _ => {
+                // the other types fit on a single line, so they're parsed like `parse_value` does
+                let value = std::str::from_utf8(&self.buffer[start..header_end])
+                    .ok()
+                    .and_then(|line| match parse_value(line) {
+                        Ok(("", value)) => Some(OwnedValue::from(value)),
+                        _ => None,
+                    })
+                    .ok_or(ParseError::InvalidValue { offset });
+                match value {
+                    Ok(value) => self.complete(value, values),
+                    // the line is complete, so the end of the value is known
+                    Err(error) if one => self.reject(error, values),
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+        Ok(Some(header_end))
+    }
+
+    /// Adds a decoded value to the aggregate being decoded, completing all the aggregates
+    /// that don't expect more values
+    fn complete(
+        &mut self,
+        mut value: OwnedValue,
+        values: &mut Vec<Result<OwnedValue, ParseError>>,
+    ) {
+        while let Some(aggregate) = self.stack.last_mut() {
+            aggregate.values.push(value);
+            aggregate.remaining -= 1;
+            if aggregate.remaining > 0 {
+                return;
+            }
+            value = self.stack.pop().unwrap().into_value();
+        }
+        values.push(match self.invalid.take() {
+            Some(error) => Err(error),
+            None => Ok(value),
+        });
+    }
+
+    /// Replaces an invalid value with a placeholder, so that the value containing it is still
+    /// decoded until its end and then returned as the (first) error
+    fn reject(&mut self, error: ParseError, values: &mut Vec<Result<OwnedValue, ParseError>>) {
+        self.invalid.get_or_insert(error);
+        self.complete(OwnedValue::Null, values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_in_chunks(input: &[u8], size: usize) -> Result<Vec<OwnedValue>, ParseError> {
+        let mut decoder = Decoder::new();
+        let mut values = Vec::new();
+        for chunk in input.chunks(size) {
+            values.extend(decoder.feed(chunk)?);
+        }
+        assert!(!decoder.in_frame());
+        Ok(values)
+    }
+
+    #[test]
+    fn test_decode_in_chunks() {
+        let input = b"*3\r\n$5\r\nhello\r\n%1\r\n+key\r\n~2\r\n:1\r\n(12\r\n,1.5\r\n\
+            =10\r\ntxt:hello!\r\n!3\r\nERR\r\n$-1\r\n*-1\r\n_\r\n#t\r\n>0\r\n-ERR\r\n";
+        let expected = vec![
+            OwnedValue::Array(vec![
+                OwnedValue::BulkString("hello".to_string()),
+                OwnedValue::Map(
+                    vec![OwnedValue::SimpleString("key".to_string())],
+                    vec![OwnedValue::Set(
+                        [
+                            OwnedValue::Integer(1),
+                            OwnedValue::BigNumber("12".to_string()),
+                        ]
+                        .into(),
+                    )],
+                ),
+                OwnedValue::Double("1.5".to_string()),
+            ]),
+            OwnedValue::VerbatimString(VerbatimFormat::Txt, "hello!".to_string()),
+            OwnedValue::BulkError("ERR".to_string()),
+            OwnedValue::Null,
+            OwnedValue::Null,
+            OwnedValue::Null,
+            OwnedValue::Boolean(true),
+            OwnedValue::Pushes(vec![]),
+            OwnedValue::SimpleError("ERR".to_string()),
+        ];
+        for size in 1..=input.len() {
+            assert_eq!(
+                decode_in_chunks(input, size),
+                Ok(expected.clone()),
+                "{}",
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_inside_frame() {
+        let mut decoder = Decoder::new();
+        let content = "x".repeat(100_000);
+        assert_eq!(decoder.feed(b"*10000\r\n$100000\r\n"), Ok(vec![]));
+        for chunk in content.as_bytes().chunks(1000) {
+            assert_eq!(decoder.feed(chunk), Ok(vec![]));
+        }
+        // the content of the bulk string is not kept in the buffer
+        assert!(decoder.buffer.is_empty());
+        assert_eq!(decoder.feed(b"\r\n"), Ok(vec![]));
+        for _ in 1..9999 {
+            assert_eq!(decoder.feed(b":1\r\n"), Ok(vec![]));
+        }
+        assert_eq!(decoder.stack[0].values.len(), 9999);
+        assert_eq!(decoder.feed(b":1"), Ok(vec![]));
+        let values = decoder.feed(b"\r\n").unwrap();
+        let OwnedValue::Array(values) = &values[0] else {
+            panic!("expected an array, got {:?}", values);
+        };
+        assert_eq!(values.len(), 10000);
+        assert_eq!(values[0], OwnedValue::BulkString(content));
+        assert!(!decoder.in_frame());
+    }
+
+    #[test]
+    fn test_long_line_in_chunks() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(b"+"), Ok(vec![]));
+        for _ in 0..100 {
+            assert_eq!(decoder.feed(&[b'x'; 1000]), Ok(vec![]));
+        }
+        assert_eq!(decoder.scanned, 100_001);
+        assert_eq!(decoder.feed(b"\r"), Ok(vec![]));
+        assert_eq!(
+            decoder.feed(b"\n"),
+            Ok(vec![OwnedValue::SimpleString("x".repeat(100_000))])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        for (input, error) in [
+            (
+                &b"+OK\r\n?"[..],
+                ParseError::InvalidType {
+                    offset: 5,
+                    byte: '?',
+                },
+            ),
+            (b"*1\r\n:x\r\n", ParseError::InvalidValue { offset: 5 }),
+            (b"+O\nK\r\n", ParseError::InvalidValue { offset: 1 }),
+            (b",NAN\r\n", ParseError::InvalidValue { offset: 1 }),
+            (b"$x\r\n", ParseError::InvalidLength { offset: 1 }),
+            (b"!-1\r\n", ParseError::InvalidLength { offset: 1 }),
+            (b"=3\r\ntxt\r\n", ParseError::InvalidLength { offset: 1 }),
+            (b"$3\r\nabcd\r\n", ParseError::ExpectedCrlf { offset: 7 }),
+            (
+                b"!2\r\n\xff\xfe\r\n",
+                ParseError::InvalidValue { offset: 4 },
+            ),
+            (b"=5\r\ntxt;a\r\n", ParseError::InvalidValue { offset: 7 }),
+        ] {
+            for size in 1..=input.len() {
+                assert_eq!(
+                    decode_in_chunks(input, size),
+                    Err(error.clone()),
+                    "{:?}",
+                    input
+                );
+            }
+        }
+
+        // the decoder can't be used after an error
+        let mut decoder = Decoder::new();
+        assert!(decoder.feed(b"+OK\r\n:x\r\n").is_err());
+        assert_eq!(
+            decoder.feed(b"+OK\r\n"),
+            Err(ParseError::InvalidValue { offset: 6 })
+        );
+    }
+
+    /// Decodes the input with [Decoder::decode_value], passing again the bytes that were not consumed
+    fn decode_values(
+        input: &[u8],
+        size: usize,
+    ) -> Result<Vec<Result<OwnedValue, ParseError>>, ParseError> {
+        let mut decoder = Decoder::new();
+        let mut values = Vec::new();
+        let mut buffer = Vec::new();
+        for chunk in input.chunks(size) {
+            buffer.extend_from_slice(chunk);
+            while !buffer.is_empty() {
+                let decoded = decoder.decode_value(&buffer)?;
+                buffer.drain(..decoded.consumed);
+                match decoded.value {
+                    Some(value) => values.push(value),
+                    None => assert!(buffer.is_empty()),
+                }
+            }
+        }
+        assert!(!decoder.in_frame());
+        Ok(values)
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let input = b"*2\r\n$5\r\nhello\r\n:1\r\n+OK\r\n$3\r\n\x00\xff\x01\r\n_\r\n";
+        let expected = decode_in_chunks(input, input.len()).unwrap();
+        for size in 1..=input.len() {
+            assert_eq!(
+                decode_values(input, size),
+                Ok(expected.iter().cloned().map(Ok).collect()),
+                "{}",
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_value_skips_invalid_values() {
+        let input = b"*3\r\n+\xff\r\n:x\r\n!2\r\n\xff\xfe\r\n+OK\r\n=5\r\ntxt;a\r\n:1\r\n";
+        for size in 1..=input.len() {
+            assert_eq!(
+                decode_values(input, size),
+                Ok(vec![
+                    // the first error of the value is returned
+                    Err(ParseError::InvalidValue { offset: 5 }),
+                    Ok(OwnedValue::SimpleString("OK".to_string())),
+                    Err(ParseError::InvalidValue { offset: 32 }),
+                    Ok(OwnedValue::Integer(1)),
+                ]),
+                "{}",
+                size
+            );
+        }
+
+        // the end of the value is unknown after the other errors
+        let mut decoder = Decoder::new();
+        assert_eq!(
+            decoder.decode_value(b"$3\r\nabcd\r\n"),
+            Err(ParseError::ExpectedCrlf { offset: 7 })
+        );
+        assert_eq!(
+            decoder.decode_value(b"+OK\r\n"),
+            Err(ParseError::ExpectedCrlf { offset: 7 })
+        );
+    }
+
+    #[test]
+    fn test_binary_bulk_strings() {
+        assert_eq!(
+            decode_in_chunks(b"*2\r\n$3\r\n\x00\xff\x01\r\n$2\r\nok\r\n", 3),
+            Ok(vec![OwnedValue::Array(vec![
+                OwnedValue::BulkBytes(vec![0, 255, 1]),
+                OwnedValue::BulkString("ok".to_string()),
+            ])])
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |depth: usize| format!("{}:1\r\n", "*1\r\n".repeat(depth));
+        assert!(decode_in_chunks(nested(MAX_DEPTH).as_bytes(), 7).is_ok());
+        assert_eq!(
+            decode_in_chunks(nested(MAX_DEPTH + 1).as_bytes(), 7),
+            Err(ParseError::TooDeep {
+                offset: MAX_DEPTH * 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_stream_to_sink() {
+        let mut input = b"*4\r\n$8\r\n".to_vec();
+        input.extend_from_slice(&[0, 159, 146, 150, 255, b'\r', b'\n', 0]);
+        input.extend_from_slice(b"\r\n$4\r\nfits\r\n!9\r\nERR large\r\n$5\r\nlarge\r\n");
+        for size in 1..=input.len() {
+            let mut decoder = Decoder::with_sink(4, Vec::new());
+            let mut values = Vec::new();
+            for chunk in input.chunks(size) {
+                values.extend(decoder.feed(chunk).unwrap());
+            }
+            assert_eq!(
+                values,
+                vec![OwnedValue::Array(vec![
+                    OwnedValue::Streamed(StreamedBulk { offset: 0, len: 8 }),
+                    OwnedValue::BulkString("fits".to_string()),
+                    OwnedValue::BulkError("ERR large".to_string()),
+                    OwnedValue::Streamed(StreamedBulk { offset: 8, len: 5 }),
+                ])]
+            );
+            assert_eq!(decoder.into_sink(), b"\0\x9f\x92\x96\xff\r\n\0large");
+        }
+    }
+
+    #[test]
+    fn test_default_keeps_bulk_strings() {
+        let mut decoder = Decoder::default();
+        assert_eq!(
+            decoder.feed(b"$5\r\nhello\r\n"),
+            Ok(vec![OwnedValue::BulkString("hello".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_streamed_as_value() {
+        let streamed = OwnedValue::Streamed(StreamedBulk { offset: 0, len: 5 });
+        assert_eq!(streamed.as_value(), crate::Value::Null);
+    }
+
+    #[test]
+    fn test_sink_errors() {
+        let mut decoder = Decoder::with_sink(2, Vec::new());
+        assert_eq!(decoder.feed(b"$3\r\nabc"), Ok(vec![]));
+        assert_eq!(decoder.sink(), b"abc");
+        assert_eq!(
+            decoder.feed(b"d\r\n"),
+            Err(ParseError::ExpectedCrlf { offset: 7 })
+        );
+
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::StorageFull.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut decoder = Decoder::with_sink(2, Full);
+        assert_eq!(
+            decoder.feed(b"$2\r\nok\r\n"),
+            Ok(vec![OwnedValue::BulkString("ok".to_string())])
+        );
+        assert_eq!(
+            decoder.feed(b"$3\r\nabc\r\n"),
+            Err(ParseError::Sink {
+                offset: 12,
+                kind: io::ErrorKind::StorageFull
+            })
+        );
+    }
+}
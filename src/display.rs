@@ -0,0 +1,202 @@
+use crate::{OwnedValue, Value};
+use std::fmt;
+
+/// Quotes a string like `redis-cli` does, escaping the non-printable bytes
+fn quoted(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    for &byte in bytes {
+        match byte {
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x07 => out.push_str("\\a"),
+            0x08 => out.push_str("\\b"),
+            0x20..=0x7e => out.push(byte as char),
+            byte => out.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Renders the items of an aggregate type (with their values, for maps), numbering them.
+///
+/// Like `redis-cli` does, every line but the first is preceded by the prefix of the parent,
+/// and the nested aggregates (including the values of maps) are indented after the numbers.
+fn aggregate<'a, 'v: 'a>(
+    out: &mut String,
+    prefix: &str,
+    items: impl ExactSizeIterator<Item = (&'a Value<'v>, Option<&'a Value<'v>>)>,
+    separator: char,
+) {
+    let width = items.len().to_string().len();
+    let nested = format!("{}{}", prefix, " ".repeat(width + 2));
+    for (i, (item, value)) in items.enumerate() {
+        if i > 0 {
+            out.push('\n');
+            out.push_str(prefix);
+        }
+        out.push_str(&format!("{:>width$}{} ", i + 1, separator));
+        render(out, item, &nested);
+        if let Some(value) = value {
+            out.push_str(" => ");
+            render(out, value, &nested);
+        }
+    }
+}
+
+/// Renders the value, preceding its lines (except the first one) by the given prefix.
+/// Strings are written as they are, so their lines are never indented.
+fn render(out: &mut String, value: &Value<'_>, prefix: &str) {
+    match value {
+        Value::SimpleString(s) => out.push_str(s),
+        Value::SimpleError(s) | Value::BulkError(s) => out.push_str(&format!("(error) {}", s)),
+        Value::Integer(i) => out.push_str(&format!("(integer) {}", i)),
+        Value::BulkString(s) => out.push_str(&quoted(s.as_bytes())),
+        Value::BulkBytes(bytes) => out.push_str(&quoted(bytes)),
+        Value::Null => out.push_str("(nil)"),
+        Value::Boolean(b) => out.push_str(&format!("({})", b)),
+        Value::Double(d) => out.push_str(&format!("(double) {}", d)),
+        Value::BigNumber(n) => out.push_str(&format!("(big number) {}", n)),
+        Value::VerbatimString(_, s) => out.push_str(s),
+        Value::Array(values) if values.is_empty() => out.push_str("(empty array)"),
+        Value::Set(values) if values.is_empty() => out.push_str("(empty set)"),
+        Value::Map(keys, _) if keys.is_empty() => out.push_str("(empty hash)"),
+        Value::Pushes(values) if values.is_empty() => out.push_str("(empty push)"),
+        Value::Array(values) | Value::Pushes(values) => {
+            aggregate(out, prefix, values.iter().map(|v| (v, None)), ')')
+        }
+        Value::Set(values) => aggregate(out, prefix, values.iter().map(|v| (v, None)), '~'),
+        Value::Map(keys, values) => aggregate(
+            out,
+            prefix,
+            keys.iter().zip(values).map(|(k, v)| (k, Some(v))),
+            '#',
+        ),
+    }
+}
+
+/// Renders the value like `redis-cli` does
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::parse;
+///
+/// let value = parse("*3\r\n:1\r\n*2\r\n$5\r\nhello\r\n_\r\n%1\r\n+key\r\n,3.14\r\n").unwrap();
+/// assert_eq!(
+///     value.to_string(),
+///     "1) (integer) 1\n2) 1) \"hello\"\n   2) (nil)\n3) 1# key => (double) 3.14"
+/// );
+/// ```
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        render(&mut out, self, "");
+        f.write_str(&out)
+    }
+}
+
+/// Renders the value like `redis-cli` does (see the [Display](fmt::Display) implementation of [Value])
+impl fmt::Display for OwnedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_value().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VerbatimFormat;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_display_simple_types() {
+        assert_eq!(Value::SimpleString("OK").to_string(), "OK");
+        assert_eq!(Value::SimpleError("ERR no").to_string(), "(error) ERR no");
+        assert_eq!(Value::BulkError("SYNTAX x").to_string(), "(error) SYNTAX x");
+        assert_eq!(Value::Integer(5).to_string(), "(integer) 5");
+        assert_eq!(Value::BulkString("hello").to_string(), "\"hello\"");
+        assert_eq!(Value::Null.to_string(), "(nil)");
+        assert_eq!(Value::Boolean(true).to_string(), "(true)");
+        assert_eq!(
+            Value::Double("3.14".to_string()).to_string(),
+            "(double) 3.14"
+        );
+        assert_eq!(Value::BigNumber("123").to_string(), "(big number) 123");
+        assert_eq!(
+            Value::VerbatimString(VerbatimFormat::Txt, "a\nb").to_string(),
+            "a\nb"
+        );
+    }
+
+    #[test]
+    fn test_display_escaping() {
+        assert_eq!(
+            Value::BulkString("a\"b\\c\r\n\t\x07\x08\x00\x7fé").to_string(),
+            "\"a\\\"b\\\\c\\r\\n\\t\\a\\b\\x00\\x7f\\xc3\\xa9\""
+        );
+    }
+
+    #[test]
+    fn test_display_aggregates() {
+        assert_eq!(Value::Array(vec![]).to_string(), "(empty array)");
+        assert_eq!(Value::Set(BTreeSet::new()).to_string(), "(empty set)");
+        assert_eq!(Value::Map(vec![], vec![]).to_string(), "(empty hash)");
+        assert_eq!(Value::Pushes(vec![]).to_string(), "(empty push)");
+
+        let value = Value::Array((1..=10).map(Value::Integer).collect());
+        assert_eq!(
+            value.to_string().lines().skip(8).collect::<Vec<_>>(),
+            vec![" 9) (integer) 9", "10) (integer) 10"]
+        );
+
+        let value = Value::Map(
+            vec![Value::BulkString("k"), Value::BulkString("nested")],
+            vec![
+                Value::BulkString("v"),
+                Value::Set(BTreeSet::from([Value::Integer(1), Value::Integer(2)])),
+            ],
+        );
+        assert_eq!(
+            value.to_string(),
+            "1# \"k\" => \"v\"\n2# \"nested\" => 1~ (integer) 1\n   2~ (integer) 2"
+        );
+        assert_eq!(OwnedValue::from(&value).to_string(), value.to_string());
+
+        // the values of nested maps are indented after the numbers of their parents
+        let value = Value::Array(vec![
+            Value::Integer(1),
+            Value::Map(
+                vec![Value::BulkString("outer")],
+                vec![Value::Map(
+                    vec![Value::BulkString("a"), Value::BulkString("b")],
+                    vec![
+                        Value::Integer(1),
+                        Value::Array(vec![Value::Null, Value::Null]),
+                    ],
+                )],
+            ),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            "1) (integer) 1\n\
+             2) 1# \"outer\" => 1# \"a\" => (integer) 1\n\
+             \x20     2# \"b\" => 1) (nil)\n\
+             \x20        2) (nil)"
+        );
+    }
+
+    #[test]
+    fn test_display_multiline_strings() {
+        // the lines of strings are not indented and keep their line endings
+        let value = Value::Array(vec![
+            Value::VerbatimString(VerbatimFormat::Txt, "a\r\nb\nc"),
+            Value::Array(vec![Value::SimpleString("x\ny")]),
+        ]);
+        assert_eq!(value.to_string(), "1) a\r\nb\nc\n2) 1) x\ny");
+    }
+}
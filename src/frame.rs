@@ -0,0 +1,343 @@
+use crate::{is_i64, parse_length, strict_double, Length, VerbatimFormat, MAX_DEPTH};
+use thiserror::Error;
+
+/// Represents an error found while scanning a RESP frame with [frame_len] or decoding it with a [Decoder](crate::Decoder).
+/// The offsets are relative to the start of the scanned buffer (or of the stream for the decoder).
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    #[error("Invalid type byte {byte:?} at offset {offset}")]
+    InvalidType { offset: usize, byte: char },
+    #[error("Invalid length at offset {offset}")]
+    InvalidLength { offset: usize },
+    #[error("Invalid value at offset {offset}")]
+    InvalidValue { offset: usize },
+    #[error("Expected CRLF at offset {offset}")]
+    ExpectedCrlf { offset: usize },
+    /// Aggregates nested deeper than [MAX_DEPTH]
+    #[error("Nesting too deep at offset {offset}")]
+    TooDeep { offset: usize },
+    /// Writing a streamed bulk string to the sink of the decoder failed
+    #[error("Failed to write to the sink at offset {offset}: {kind}")]
+    Sink {
+        offset: usize,
+        kind: std::io::ErrorKind,
+    },
+}
+
+/// Finds the position of the CRLF terminating the line starting at `start`
+fn line_end(buffer: &[u8], start: usize) -> Option<usize> {
+    memchr::memmem::find(buffer.get(start..)?, b"\r\n").map(|pos| start + pos)
+}
+
+/// Checks if the line is a big number (an integer of any size)
+fn is_big_number(line: &[u8]) -> bool {
+    let digits = match line.first() {
+        Some(b'+' | b'-') => &line[1..],
+        _ => line,
+    };
+    !digits.is_empty() && digits.iter().all(u8::is_ascii_digit)
+}
+
+/// Parses a length header, returning `None` for the `-1` null length (only allowed if `nullable`)
+fn length(line: &[u8], offset: usize, nullable: bool) -> Result<Option<usize>, ParseError> {
+    match parse_length(line, nullable, false) {
+        Some(Length::Null) => Ok(None),
+        Some(Length::Count(len)) => Ok(Some(len)),
+        None => Err(ParseError::InvalidLength { offset }),
+    }
+}
+
+/// Validates and measures the first RESP frame in the given buffer, without decoding it.
+///
+/// Returns the length in bytes of the frame (including all the nested values of aggregate types)
+/// or `None` if the buffer doesn't contain a complete frame yet. This doesn't allocate and it's much
+/// faster than parsing the frame, so it can be used to split a stream in frames (e.g. in a proxy)
+/// or to know when enough data has been received before calling [parse_value](crate::parse_value).
+///
+/// The header lines are validated like the parser does (including the nesting depth),
+/// but the content of strings is not (e.g. it may not be valid UTF-8).
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{frame_len, ParseError};
+///
+/// assert_eq!(frame_len(b"*2\r\n$5\r\nhello\r\n:1\r\n+OK\r\n"), Ok(Some(19)));
+/// assert_eq!(frame_len(b"*2\r\n$5\r\nhello\r\n"), Ok(None));
+/// assert_eq!(
+///     frame_len(b"*2\r\n:x\r\n"),
+///     Err(ParseError::InvalidValue { offset: 5 })
+/// );
+/// ```
+pub fn frame_len(buffer: &[u8]) -> Result<Option<usize>, ParseError> {
+    // the number of values still to be scanned in each of the open aggregates
+    let mut open = [0usize; MAX_DEPTH];
+    let mut depth = 0;
+    let mut pos = 0;
+    loop {
+        let start = pos;
+        let Some(&prefix) = buffer.get(start) else {
+            return Ok(None);
+        };
+        if !b"+-:$*_#,(!=%~>".contains(&prefix) {
+            return Err(ParseError::InvalidType {
+                offset: start,
+                byte: prefix as char,
+            });
+        }
+        let Some(line_end) = line_end(buffer, start + 1) else {
+            return Ok(None);
+        };
+        let line = &buffer[start + 1..line_end];
+        let offset = start + 1;
+        let invalid = ParseError::InvalidValue { offset };
+        let header_end = line_end + 2;
+        pos = header_end;
+
+        match prefix {
+            b'+' | b'-' => {
+                if line.contains(&b'\r') || line.contains(&b'\n') {
+                    return Err(invalid);
+                }
+            }
+            b':' => {
+                if !std::str::from_utf8(line).is_ok_and(is_i64) {
+                    return Err(invalid);
+                }
+            }
+            b'(' => {
+                if !is_big_number(line) {
+                    return Err(invalid);
+                }
+            }
+            b'_' => {
+                if !line.is_empty() {
+                    return Err(invalid);
+                }
+            }
+            b'#' => {
+                if line != b"t" && line != b"f" {
+                    return Err(invalid);
+                }
+            }
+            b',' => {
+                let valid = std::str::from_utf8(line)
+                    .ok()
+                    .and_then(strict_double)
+                    .is_some();
+                if !valid {
+                    return Err(invalid);
+                }
+            }
+            b'$' | b'!' | b'=' => {
+                if let Some(len) = length(line, offset, prefix == b'$')? {
+                    if prefix == b'=' && len < 4 {
+                        return Err(ParseError::InvalidLength { offset });
+                    }
+                    let end = header_end.saturating_add(len);
+                    if buffer.len() < end.saturating_add(2) {
+                        return Ok(None);
+                    }
+                    if prefix == b'=' {
+                        let format = std::str::from_utf8(&buffer[header_end..header_end + 3])
+                            .ok()
+                            .and_then(VerbatimFormat::from_prefix);
+                        if format.is_none() {
+                            return Err(ParseError::InvalidValue { offset: header_end });
+                        }
+                        if buffer[header_end + 3] != b':' {
+                            return Err(ParseError::InvalidValue {
+                                offset: header_end + 3,
+                            });
+                        }
+                    }
+                    if &buffer[end..end + 2] != b"\r\n" {
+                        return Err(ParseError::ExpectedCrlf { offset: end });
+                    }
+                    pos = end + 2;
+                }
+            }
+            _ => {
+                // aggregate types (`*`, `~`, `>` and `%`), which count in the depth even if empty
+                if depth == MAX_DEPTH {
+                    return Err(ParseError::TooDeep { offset: start });
+                }
+                let len = length(line, offset, prefix == b'*')?.unwrap_or(0);
+                let count = if prefix == b'%' {
+                    len.saturating_mul(2)
+                } else {
+                    len
+                };
+                if count > 0 {
+                    open[depth] = count;
+                    depth += 1;
+                    continue;
+                }
+            }
+        }
+
+        // a whole value has been scanned, close the aggregates that are complete
+        loop {
+            if depth == 0 {
+                return Ok(Some(pos));
+            }
+            open[depth - 1] -= 1;
+            if open[depth - 1] > 0 {
+                break;
+            }
+            depth -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_value;
+
+    #[test]
+    fn test_frame_len() {
+        assert_eq!(frame_len(b"").unwrap(), None);
+        assert_eq!(frame_len(b"+OK").unwrap(), None);
+        assert_eq!(frame_len(b"+OK\r\n+NEXT").unwrap(), Some(5));
+        assert_eq!(frame_len(b"$5\r\nhel").unwrap(), None);
+        assert_eq!(frame_len(b"$5\r\nhello\r\n").unwrap(), Some(11));
+        assert_eq!(frame_len(b"$-1\r\n").unwrap(), Some(5));
+        assert_eq!(frame_len(b"*-1\r\n").unwrap(), Some(5));
+        assert_eq!(frame_len(b"*2\r\n:1\r\n").unwrap(), None);
+        assert_eq!(frame_len(b"*2\r\n:1\r\n:2\r\n").unwrap(), Some(12));
+        assert_eq!(frame_len(b"%1\r\n:1\r\n").unwrap(), None);
+        assert_eq!(
+            frame_len(b"*1\r\n?").unwrap_err(),
+            ParseError::InvalidType {
+                offset: 4,
+                byte: '?'
+            }
+        );
+        assert_eq!(frame_len(b"$9223372036854775807\r\n").unwrap(), None);
+    }
+
+    #[test]
+    fn test_frame_len_verbatim_format() {
+        for invalid in [&b"=7\r\nt t:abc\r\n"[..], b"=7\r\nt\xffx:abc\r\n"] {
+            assert_eq!(
+                frame_len(invalid),
+                Err(ParseError::InvalidValue { offset: 4 }),
+                "{:?}",
+                invalid
+            );
+            assert!(parse_value(&String::from_utf8_lossy(invalid)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_frame_len_depth() {
+        let nested = |depth, last: &str| format!("{}{}", "*1\r\n".repeat(depth), last);
+        for last in [":1\r\n", "*0\r\n", "*-1\r\n", "%0\r\n"] {
+            let ok = nested(MAX_DEPTH - 1, last);
+            assert!(parse_value(&ok).is_ok());
+            assert_eq!(frame_len(ok.as_bytes()), Ok(Some(ok.len())));
+        }
+        let too_deep = nested(MAX_DEPTH, "*0\r\n");
+        assert!(parse_value(&too_deep).is_err());
+        assert_eq!(
+            frame_len(too_deep.as_bytes()),
+            Err(ParseError::TooDeep {
+                offset: 4 * MAX_DEPTH
+            })
+        );
+        // siblings don't add to the depth
+        let wide = format!("*3\r\n{}", nested(MAX_DEPTH - 1, ":1\r\n").repeat(3));
+        assert_eq!(frame_len(wide.as_bytes()), Ok(Some(wide.len())));
+    }
+
+    #[test]
+    fn test_frame_len_matches_parser() {
+        let messages = [
+            "+OK\r\n",
+            "-ERR unknown\r\n",
+            ":-42\r\n",
+            "$0\r\n\r\n",
+            "_\r\n",
+            "#t\r\n",
+            ",-inf\r\n",
+            ",3.14\r\n",
+            "(+12345678901234567890\r\n",
+            "!3\r\nERR\r\n",
+            "=7\r\ntxt:abc\r\n",
+            "*3\r\n*1\r\n:1\r\n%1\r\n+a\r\n~1\r\n_\r\n>0\r\n",
+        ];
+        for message in messages {
+            let (rest, _) = parse_value(message).unwrap();
+            assert_eq!(
+                frame_len(message.as_bytes()),
+                Ok(Some(message.len() - rest.len())),
+                "{:?}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn test_frame_len_errors() {
+        assert_eq!(
+            frame_len(b"*1\r\n?\r\n"),
+            Err(ParseError::InvalidType {
+                offset: 4,
+                byte: '?'
+            })
+        );
+        assert_eq!(
+            frame_len(b"$x\r\n"),
+            Err(ParseError::InvalidLength { offset: 1 })
+        );
+        assert_eq!(
+            frame_len(b"$-5\r\n"),
+            Err(ParseError::InvalidLength { offset: 1 })
+        );
+        assert_eq!(
+            frame_len(b"$9223372036854775808\r\n"),
+            Err(ParseError::InvalidLength { offset: 1 })
+        );
+        assert_eq!(
+            frame_len(b"~-1\r\n"),
+            Err(ParseError::InvalidLength { offset: 1 })
+        );
+        assert_eq!(
+            frame_len(b"$2\r\nabc\r\n"),
+            Err(ParseError::ExpectedCrlf { offset: 6 })
+        );
+        assert_eq!(
+            frame_len(b"=5\r\ntxtab\r\n"),
+            Err(ParseError::InvalidValue { offset: 7 })
+        );
+        assert_eq!(
+            frame_len(b"=3\r\ntxt\r\n"),
+            Err(ParseError::InvalidLength { offset: 1 })
+        );
+        for invalid in [
+            &b":1.5\r\n"[..],
+            b":\r\n",
+            b"#x\r\n",
+            b"_x\r\n",
+            b",abc\r\n",
+            b"(12a\r\n",
+            b"+a\nb\r\n",
+            // the values rejected by the parser
+            b":9223372036854775808\r\n",
+            b":-9223372036854775809\r\n",
+            b",infinity\r\n",
+            b",INF\r\n",
+            b",NaN\r\n",
+            b",1e\r\n",
+            b"(+\r\n",
+        ] {
+            assert_eq!(
+                frame_len(invalid),
+                Err(ParseError::InvalidValue { offset: 1 }),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+}
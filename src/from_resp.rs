@@ -0,0 +1,436 @@
+use crate::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+use thiserror::Error;
+
+/// Represents an error that can occur when converting a [Value] with [FromResp]
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum FromRespError {
+    #[error("The server replied with an error: {0}")]
+    ServerError(String),
+    #[error("Unexpected value, expected {expected} but found {found}")]
+    UnexpectedType {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("Invalid value for {expected}: {value}")]
+    InvalidValue {
+        expected: &'static str,
+        value: String,
+    },
+    #[error("Unexpected number of elements, expected {expected} but found {found}")]
+    WrongLength { expected: usize, found: usize },
+    #[error("Unpaired map entries (keys: {keys}, values: {values})")]
+    UnpairedEntries { keys: usize, values: usize },
+    #[error("Missing field: {0}")]
+    MissingField(&'static str),
+    #[error("Invalid field {field}: {source}")]
+    InvalidField {
+        field: &'static str,
+        source: Box<FromRespError>,
+    },
+}
+
+impl FromRespError {
+    /// Creates a [FromRespError::UnexpectedType] error (or a [FromRespError::ServerError]
+    /// if the given value is an error)
+    pub fn unexpected(expected: &'static str, value: &Value<'_>) -> Self {
+        match value {
+            Value::SimpleError(e) | Value::BulkError(e) => {
+                FromRespError::ServerError(e.to_string())
+            }
+            value => FromRespError::UnexpectedType {
+                expected,
+                found: format!("{:?}", value),
+            },
+        }
+    }
+}
+
+/// A trait for types that can be created from a [Value].
+///
+/// Implementations are provided for the primitive types, strings, the most common collections
+/// and tuples. When the `derive` feature is enabled, `#[derive(FromResp)]` can be used to decode
+/// a RESP3 map (or a RESP2 flat array of alternating keys and values) into a struct.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+/// use tinyresp::{parse, FromResp};
+///
+/// let value = parse("*4\r\n$4\r\nname\r\n$4\r\nJohn\r\n$3\r\nage\r\n$2\r\n42\r\n").unwrap();
+/// let hash = HashMap::<String, String>::from_resp(&value).unwrap();
+/// assert_eq!(hash["name"], "John");
+///
+/// let value = parse("*2\r\n:1\r\n_\r\n").unwrap();
+/// let pair = <(i64, Option<String>)>::from_resp(&value).unwrap();
+/// assert_eq!(pair, (1, None));
+/// ```
+pub trait FromResp<'a>: Sized {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError>;
+}
+
+impl<'a> Value<'a> {
+    /// Helper method that converts the current value into any type implementing [FromResp]
+    pub fn decode<T: FromResp<'a>>(&self) -> Result<T, FromRespError> {
+        T::from_resp(self)
+    }
+}
+
+impl<'a> FromResp<'a> for Value<'a> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        Ok(v.clone())
+    }
+}
+
+macro_rules! impl_from_resp_integer {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> FromResp<'a> for $t {
+                fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+                    let invalid = || FromRespError::InvalidValue {
+                        expected: stringify!($t),
+                        value: format!("{:?}", v),
+                    };
+                    match v {
+                        Value::Integer(i) => <$t>::try_from(*i).map_err(|_| invalid()),
+                        Value::SimpleString(s) | Value::BulkString(s) | Value::BigNumber(s) => {
+                            s.parse().map_err(|_| invalid())
+                        }
+                        v => Err(FromRespError::unexpected(stringify!($t), v)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_resp_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_from_resp_float {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> FromResp<'a> for $t {
+                fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+                    let s = match v {
+                        Value::Integer(i) => return Ok(*i as $t),
+                        Value::Double(s) => s.as_str(),
+                        Value::SimpleString(s) | Value::BulkString(s) | Value::BigNumber(s) => s,
+                        v => return Err(FromRespError::unexpected(stringify!($t), v)),
+                    };
+                    s.parse().map_err(|_| FromRespError::InvalidValue {
+                        expected: stringify!($t),
+                        value: s.to_string(),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_from_resp_float!(f32, f64);
+
+impl<'a> FromResp<'a> for bool {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        match v {
+            Value::Boolean(b) => Ok(*b),
+            Value::Integer(0) => Ok(false),
+            Value::Integer(1) => Ok(true),
+            v => Err(FromRespError::unexpected("bool", v)),
+        }
+    }
+}
+
+impl<'a> FromResp<'a> for String {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        match v {
+            Value::Integer(i) => Ok(i.to_string()),
+            v if v.is_err() => Err(FromRespError::unexpected("String", v)),
+            v => v
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| FromRespError::unexpected("String", v)),
+        }
+    }
+}
+
+impl<'a> FromResp<'a> for &'a str {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        match v {
+            Value::SimpleString(s)
+            | Value::BulkString(s)
+            | Value::BigNumber(s)
+            | Value::VerbatimString(_, s) => Ok(s),
+            v => Err(FromRespError::unexpected("&str", v)),
+        }
+    }
+}
+
+impl<'a, T: FromResp<'a>> FromResp<'a> for Option<T> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        match v {
+            Value::Null => Ok(None),
+            v => T::from_resp(v).map(Some),
+        }
+    }
+}
+
+/// Returns the elements of an aggregate value (array, set or push)
+fn elements<'a, 'b>(
+    expected: &'static str,
+    v: &'b Value<'a>,
+) -> Result<Box<dyn Iterator<Item = &'b Value<'a>> + 'b>, FromRespError> {
+    match v {
+        Value::Array(values) | Value::Pushes(values) => Ok(Box::new(values.iter())),
+        Value::Set(values) => Ok(Box::new(values.iter())),
+        v => Err(FromRespError::unexpected(expected, v)),
+    }
+}
+
+/// Returns the key/value pairs of a RESP3 map or of a RESP2 flat array of alternating keys and values.
+///
+/// This function is used by the code generated by `#[derive(FromResp)]` and it's exposed only for that purpose.
+#[doc(hidden)]
+pub fn map_entries<'a, 'b>(
+    expected: &'static str,
+    v: &'b Value<'a>,
+) -> Result<Vec<(&'b Value<'a>, &'b Value<'a>)>, FromRespError> {
+    match v {
+        Value::Map(keys, values) if keys.len() == values.len() => {
+            Ok(keys.iter().zip(values.iter()).collect())
+        }
+        Value::Map(keys, values) => Err(FromRespError::UnpairedEntries {
+            keys: keys.len(),
+            values: values.len(),
+        }),
+        Value::Array(values) if values.len() % 2 == 0 => Ok(values
+            .chunks_exact(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .collect()),
+        // the last key of the flat array has no value
+        Value::Array(values) => Err(FromRespError::UnpairedEntries {
+            keys: values.len() / 2 + 1,
+            values: values.len() / 2,
+        }),
+        v => Err(FromRespError::unexpected(expected, v)),
+    }
+}
+
+impl<'a, T: FromResp<'a>> FromResp<'a> for Vec<T> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        elements("Vec", v)?.map(T::from_resp).collect()
+    }
+}
+
+impl<'a, T: FromResp<'a> + Ord> FromResp<'a> for BTreeSet<T> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        elements("BTreeSet", v)?.map(T::from_resp).collect()
+    }
+}
+
+impl<'a, T: FromResp<'a> + Eq + Hash> FromResp<'a> for HashSet<T> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        elements("HashSet", v)?.map(T::from_resp).collect()
+    }
+}
+
+impl<'a, K: FromResp<'a> + Eq + Hash, V: FromResp<'a>> FromResp<'a> for HashMap<K, V> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        map_entries("HashMap", v)?
+            .into_iter()
+            .map(|(k, v)| Ok((K::from_resp(k)?, V::from_resp(v)?)))
+            .collect()
+    }
+}
+
+impl<'a, K: FromResp<'a> + Ord, V: FromResp<'a>> FromResp<'a> for BTreeMap<K, V> {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        map_entries("BTreeMap", v)?
+            .into_iter()
+            .map(|(k, v)| Ok((K::from_resp(k)?, V::from_resp(v)?)))
+            .collect()
+    }
+}
+
+macro_rules! impl_from_resp_tuple {
+    ($len:expr => $($name:ident),+) => {
+        impl<'a, $($name: FromResp<'a>),+> FromResp<'a> for ($($name,)+) {
+            fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+                match v {
+                    Value::Array(values) | Value::Pushes(values) => {
+                        if values.len() != $len {
+                            return Err(FromRespError::WrongLength {
+                                expected: $len,
+                                found: values.len(),
+                            });
+                        }
+                        let mut values = values.iter();
+                        Ok(($($name::from_resp(values.next().unwrap())?,)+))
+                    }
+                    v => Err(FromRespError::unexpected("tuple", v)),
+                }
+            }
+        }
+    };
+}
+
+impl_from_resp_tuple!(1 => A);
+impl_from_resp_tuple!(2 => A, B);
+impl_from_resp_tuple!(3 => A, B, C);
+impl_from_resp_tuple!(4 => A, B, C, D);
+impl_from_resp_tuple!(5 => A, B, C, D, E);
+impl_from_resp_tuple!(6 => A, B, C, D, E, F);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, VerbatimFormat};
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(i64::from_resp(&Value::Integer(-42)), Ok(-42));
+        assert_eq!(u8::from_resp(&Value::Integer(42)), Ok(42));
+        assert_eq!(u64::from_resp(&Value::BulkString("42")), Ok(42));
+        assert_eq!(
+            i128::from_resp(&Value::BigNumber("3492890328409238509324850943850943")),
+            Ok(3492890328409238509324850943850943)
+        );
+        assert!(matches!(
+            u8::from_resp(&Value::Integer(-1)),
+            Err(FromRespError::InvalidValue { expected: "u8", .. })
+        ));
+        assert!(matches!(
+            i64::from_resp(&Value::BulkString("abc")),
+            Err(FromRespError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            i64::from_resp(&Value::Null),
+            Err(FromRespError::UnexpectedType { .. })
+        ));
+        assert_eq!(
+            i64::from_resp(&Value::SimpleError("ERR boom")),
+            Err(FromRespError::ServerError("ERR boom".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(f64::from_resp(&Value::Double("1.5".to_string())), Ok(1.5));
+        assert_eq!(f64::from_resp(&Value::BulkString("inf")), Ok(f64::INFINITY));
+        assert_eq!(f32::from_resp(&Value::Integer(2)), Ok(2.0));
+        assert!(f64::from_resp(&Value::BulkString("abc")).is_err());
+        assert!(f64::from_resp(&Value::Boolean(true)).is_err());
+    }
+
+    #[test]
+    fn test_bool() {
+        assert_eq!(bool::from_resp(&Value::Boolean(true)), Ok(true));
+        assert_eq!(bool::from_resp(&Value::Integer(0)), Ok(false));
+        assert_eq!(bool::from_resp(&Value::Integer(1)), Ok(true));
+        assert!(bool::from_resp(&Value::Integer(2)).is_err());
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            String::from_resp(&Value::SimpleString("OK")),
+            Ok("OK".to_string())
+        );
+        assert_eq!(String::from_resp(&Value::Integer(1)), Ok("1".to_string()));
+        assert_eq!(
+            String::from_resp(&Value::VerbatimString(VerbatimFormat::Txt, "hello")),
+            Ok("hello".to_string())
+        );
+        assert!(String::from_resp(&Value::BulkError("ERR boom")).is_err());
+        assert!(String::from_resp(&Value::Null).is_err());
+
+        assert_eq!(<&str>::from_resp(&Value::BulkString("hello")), Ok("hello"));
+        assert!(<&str>::from_resp(&Value::Double("1.5".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_option() {
+        assert_eq!(Option::<i64>::from_resp(&Value::Null), Ok(None));
+        assert_eq!(Option::<i64>::from_resp(&Value::Integer(1)), Ok(Some(1)));
+    }
+
+    #[test]
+    fn test_collections() {
+        let value = parse("*3\r\n:1\r\n:2\r\n:2\r\n").unwrap();
+        assert_eq!(Vec::<i64>::from_resp(&value), Ok(vec![1, 2, 2]));
+        assert_eq!(
+            BTreeSet::<i64>::from_resp(&value),
+            Ok(BTreeSet::from([1, 2]))
+        );
+        assert_eq!(HashSet::<i64>::from_resp(&value), Ok(HashSet::from([1, 2])));
+
+        let value = parse("~2\r\n+a\r\n+b\r\n").unwrap();
+        assert_eq!(
+            BTreeSet::<String>::from_resp(&value),
+            Ok(BTreeSet::from(["a".to_string(), "b".to_string()]))
+        );
+
+        let value = parse("*2\r\n:1\r\n+a\r\n").unwrap();
+        assert!(Vec::<i64>::from_resp(&value).is_err());
+        assert!(Vec::<i64>::from_resp(&Value::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn test_maps() {
+        let expected = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+
+        let value = parse("%2\r\n+a\r\n:1\r\n+b\r\n:2\r\n").unwrap();
+        assert_eq!(
+            HashMap::<String, i64>::from_resp(&value),
+            Ok(expected.clone())
+        );
+
+        let value = parse("*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n").unwrap();
+        assert_eq!(HashMap::<String, i64>::from_resp(&value), Ok(expected));
+
+        let value = parse("%1\r\n+a\r\n:1\r\n").unwrap();
+        assert_eq!(
+            BTreeMap::<&str, u8>::from_resp(&value),
+            Ok(BTreeMap::from([("a", 1)]))
+        );
+
+        let value = parse("*3\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n").unwrap();
+        assert_eq!(
+            HashMap::<String, String>::from_resp(&value),
+            Err(FromRespError::UnpairedEntries { keys: 2, values: 1 })
+        );
+        let value = Value::Map(vec![Value::BulkString("a")], vec![]);
+        assert_eq!(
+            HashMap::<String, String>::from_resp(&value),
+            Err(FromRespError::UnpairedEntries { keys: 1, values: 0 })
+        );
+        assert_eq!(
+            FromRespError::UnpairedEntries { keys: 2, values: 1 }.to_string(),
+            "Unpaired map entries (keys: 2, values: 1)"
+        );
+    }
+
+    #[test]
+    fn test_tuples() {
+        let value = parse("*3\r\n$1\r\na\r\n,1.5\r\n#t\r\n").unwrap();
+        assert_eq!(
+            <(String, f64, bool)>::from_resp(&value),
+            Ok(("a".to_string(), 1.5, true))
+        );
+        assert_eq!(
+            <(String, f64)>::from_resp(&value),
+            Err(FromRespError::WrongLength {
+                expected: 2,
+                found: 3
+            })
+        );
+
+        // ZRANGE WITHSCORES in RESP3
+        let value = parse("*2\r\n*2\r\n$1\r\na\r\n,1\r\n*2\r\n$1\r\nb\r\n,2\r\n").unwrap();
+        assert_eq!(
+            value.decode::<Vec<(&str, f64)>>(),
+            Ok(vec![("a", 1.0), ("b", 2.0)])
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{map_entries, Command, FromResp, FromRespError, Protocol, Value};
+
+/// A builder for the `HELLO` command, used to negotiate the protocol version with the server.
+///
+/// RESP3 types ([Value::Map], [Value::Set], [Value::Pushes], ...) are only sent by the server
+/// after a successful `HELLO 3`.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{Hello, Protocol, ToResp};
+///
+/// let command = Hello::new(Protocol::Resp3)
+///     .auth("default", "secret")
+///     .setname("my-app")
+///     .command();
+/// assert_eq!(
+///     command.to_resp(Protocol::Resp2),
+///     b"*7\r\n$5\r\nHELLO\r\n$1\r\n3\r\n$4\r\nAUTH\r\n$7\r\ndefault\r\n$6\r\nsecret\r\n$7\r\nSETNAME\r\n$6\r\nmy-app\r\n"
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Hello {
+    protocol: Protocol,
+    auth: Option<(String, String)>,
+    name: Option<String>,
+}
+
+impl Hello {
+    /// Creates a new `HELLO` command requesting the given protocol version
+    pub fn new(protocol: Protocol) -> Self {
+        Hello {
+            protocol,
+            auth: None,
+            name: None,
+        }
+    }
+
+    /// Authenticates the connection with the given username and password (`AUTH` option)
+    pub fn auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Sets the name of the connection (`SETNAME` option)
+    pub fn setname(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Builds the command to send to the server
+    pub fn command(&self) -> Command {
+        let version = match self.protocol {
+            Protocol::Resp2 => "2",
+            Protocol::Resp3 => "3",
+        };
+        let mut command = Command::new("HELLO").arg(version);
+        if let Some((username, password)) = &self.auth {
+            command = command.arg("AUTH").arg(username).arg(password);
+        }
+        if let Some(name) = &self.name {
+            command = command.arg("SETNAME").arg(name);
+        }
+        command
+    }
+}
+
+impl From<Hello> for Command {
+    fn from(hello: Hello) -> Self {
+        hello.command()
+    }
+}
+
+/// A module loaded by the server, as reported in the reply of `HELLO`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ServerModule {
+    pub name: String,
+    pub version: i64,
+}
+
+/// The reply of the `HELLO` command.
+///
+/// It can be decoded with [FromResp] from both the RESP2 shape (flat array of alternating keys and values)
+/// and the RESP3 shape (map) of the reply.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{parse, FromResp, Protocol, ServerHello};
+///
+/// let reply = parse("%7\r\n$6\r\nserver\r\n$5\r\nredis\r\n$7\r\nversion\r\n$5\r\n7.2.4\r\n$5\r\nproto\r\n:3\r\n$2\r\nid\r\n:5\r\n$4\r\nmode\r\n$10\r\nstandalone\r\n$4\r\nrole\r\n$6\r\nmaster\r\n$7\r\nmodules\r\n*0\r\n").unwrap();
+/// let hello = ServerHello::from_resp(&reply).unwrap();
+/// assert_eq!(hello.version, "7.2.4");
+/// assert_eq!(hello.proto, Protocol::Resp3);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ServerHello {
+    pub server: String,
+    pub version: String,
+    /// The protocol version negotiated for the connection
+    pub proto: Protocol,
+    /// The id of the connection (the same reported by `CLIENT ID`)
+    pub id: i64,
+    pub mode: String,
+    pub role: String,
+    pub modules: Vec<ServerModule>,
+}
+
+fn field<'a, T: FromResp<'a>>(
+    entries: &[(&Value<'a>, &Value<'a>)],
+    name: &'static str,
+) -> Result<T, FromRespError> {
+    let value = entries
+        .iter()
+        .find(|(key, _)| key.as_str() == Some(name))
+        .map(|(_, value)| *value)
+        .unwrap_or(&Value::Null);
+    match value {
+        Value::Null => T::from_resp(value).map_err(|_| FromRespError::MissingField(name)),
+        value => T::from_resp(value).map_err(|e| FromRespError::InvalidField {
+            field: name,
+            source: Box::new(e),
+        }),
+    }
+}
+
+impl<'a> FromResp<'a> for ServerModule {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        let entries = map_entries("ServerModule", v)?;
+        Ok(ServerModule {
+            name: field(&entries, "name")?,
+            version: field(&entries, "ver")?,
+        })
+    }
+}
+
+impl<'a> FromResp<'a> for ServerHello {
+    fn from_resp(v: &Value<'a>) -> Result<Self, FromRespError> {
+        let entries = map_entries("ServerHello", v)?;
+        let proto = match field::<i64>(&entries, "proto")? {
+            2 => Protocol::Resp2,
+            3 => Protocol::Resp3,
+            proto => {
+                return Err(FromRespError::InvalidValue {
+                    expected: "a protocol version",
+                    value: proto.to_string(),
+                })
+            }
+        };
+        Ok(ServerHello {
+            server: field(&entries, "server")?,
+            version: field(&entries, "version")?,
+            proto,
+            id: field(&entries, "id")?,
+            mode: field(&entries, "mode")?,
+            role: field(&entries, "role")?,
+            modules: field::<Option<_>>(&entries, "modules")?.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, ToResp};
+
+    const FIELDS: &str = "$6\r\nserver\r\n$5\r\nredis\r\n$7\r\nversion\r\n$5\r\n7.2.4\r\n$5\r\nproto\r\n:2\r\n$2\r\nid\r\n:5\r\n$4\r\nmode\r\n$10\r\nstandalone\r\n$4\r\nrole\r\n$6\r\nmaster\r\n$7\r\nmodules\r\n*1\r\n*4\r\n$4\r\nname\r\n$6\r\nsearch\r\n$3\r\nver\r\n:20809\r\n";
+
+    fn expected(proto: Protocol) -> ServerHello {
+        ServerHello {
+            server: "redis".to_string(),
+            version: "7.2.4".to_string(),
+            proto,
+            id: 5,
+            mode: "standalone".to_string(),
+            role: "master".to_string(),
+            modules: vec![ServerModule {
+                name: "search".to_string(),
+                version: 20809,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_hello_command() {
+        assert_eq!(
+            Hello::new(Protocol::Resp2).command(),
+            Command::new("HELLO").arg("2")
+        );
+        assert_eq!(
+            Command::from(Hello::new(Protocol::Resp3).setname("app")),
+            Command::new("HELLO").args(["3", "SETNAME", "app"])
+        );
+        assert_eq!(
+            Hello::new(Protocol::Resp3)
+                .auth("user", "pass")
+                .command()
+                .to_resp(Protocol::Resp2),
+            b"*5\r\n$5\r\nHELLO\r\n$1\r\n3\r\n$4\r\nAUTH\r\n$4\r\nuser\r\n$4\r\npass\r\n"
+        );
+    }
+
+    #[test]
+    fn test_server_hello_resp2() {
+        let message = format!("*14\r\n{FIELDS}");
+        let value = parse(&message).unwrap();
+        assert_eq!(
+            ServerHello::from_resp(&value),
+            Ok(expected(Protocol::Resp2))
+        );
+    }
+
+    #[test]
+    fn test_server_hello_resp3() {
+        let fields = FIELDS
+            .replace(":2\r\n", ":3\r\n")
+            .replace("*4\r\n$4\r\nname", "%2\r\n$4\r\nname");
+        let message = format!("%7\r\n{fields}");
+        let value = parse(&message).unwrap();
+        assert_eq!(
+            ServerHello::from_resp(&value),
+            Ok(expected(Protocol::Resp3))
+        );
+    }
+
+    #[test]
+    fn test_server_hello_errors() {
+        let value = parse("%1\r\n$6\r\nserver\r\n$5\r\nredis\r\n").unwrap();
+        assert_eq!(
+            ServerHello::from_resp(&value),
+            Err(FromRespError::MissingField("proto"))
+        );
+
+        let value = parse("%1\r\n$5\r\nproto\r\n:4\r\n").unwrap();
+        assert!(matches!(
+            ServerHello::from_resp(&value),
+            Err(FromRespError::InvalidValue { .. })
+        ));
+
+        let value = Value::SimpleError("NOPROTO unsupported protocol version");
+        assert_eq!(
+            ServerHello::from_resp(&value),
+            Err(FromRespError::ServerError(
+                "NOPROTO unsupported protocol version".to_string()
+            ))
+        );
+    }
+}
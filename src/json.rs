@@ -0,0 +1,379 @@
+use crate::{Value, VerbatimFormat};
+use serde_json::{json, Number};
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Represents an error that can occur when converting a JSON document to a [Value]
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FromJsonError {
+    #[error("Unknown RESP type: {0}")]
+    UnknownType(String),
+    #[error("Invalid node for the RESP type {0}")]
+    InvalidNode(&'static str),
+}
+
+fn double_to_json(d: &str) -> serde_json::Value {
+    // JSON has no representation for inf and nan
+    match d.parse::<f64>().ok().and_then(Number::from_f64) {
+        Some(n) => serde_json::Value::Number(n),
+        None => serde_json::Value::String(d.to_string()),
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Converts the value to JSON.
+    ///
+    /// The conversion is lossy:
+    /// - strings ([Value::SimpleString], [Value::BulkString] and [Value::VerbatimString]) become JSON strings
+    /// - errors ([Value::SimpleError] and [Value::BulkError]) become `{"error": "..."}`
+    /// - [Value::Integer] and [Value::Double] become numbers (infinity and NaN become strings)
+    /// - [Value::BigNumber] becomes a string, so that no precision is lost
+    /// - [Value::Null] becomes `null` and [Value::Boolean] a boolean
+    /// - [Value::Array], [Value::Set] and [Value::Pushes] become arrays
+    /// - [Value::Map] becomes an object if all the keys are unique strings, otherwise an array of `[key, value]` pairs
+    ///   (the objects keep the order of the keys only if the `preserve_order` feature of `serde_json` is enabled)
+    /// - [Value::BulkBytes] becomes a string, replacing the invalid UTF-8 sequences with `U+FFFD`
+    ///
+    /// Use [Value::to_json_lossless] if the conversion needs to be reversed exactly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::parse;
+    ///
+    /// let value = parse("%2\r\n+name\r\n$5\r\nhello\r\n+tags\r\n~2\r\n:1\r\n_\r\n").unwrap();
+    /// assert_eq!(value.to_json().to_string(), r#"{"name":"hello","tags":[1,null]}"#);
+    /// ```
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::SimpleString(s) | Value::BulkString(s) | Value::VerbatimString(_, s) => {
+                json!(s)
+            }
+            Value::SimpleError(s) | Value::BulkError(s) => json!({ "error": s }),
+            Value::Integer(i) => json!(i),
+            Value::Double(d) => double_to_json(d),
+            Value::BigNumber(n) => json!(n),
+            Value::Null => serde_json::Value::Null,
+            Value::Boolean(b) => json!(b),
+            Value::Array(values) | Value::Pushes(values) => {
+                values.iter().map(Value::to_json).collect()
+            }
+            Value::Set(values) => values.iter().map(Value::to_json).collect(),
+            Value::BulkBytes(bytes) => json!(String::from_utf8_lossy(bytes)),
+            Value::Map(keys, values) => {
+                let mut unique = HashSet::new();
+                let string_keys: Option<Vec<&str>> = keys
+                    .iter()
+                    .map(|key| match key {
+                        Value::SimpleString(s) | Value::BulkString(s) if unique.insert(*s) => {
+                            Some(*s)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                match string_keys {
+                    Some(keys) => serde_json::Value::Object(
+                        keys.into_iter()
+                            .zip(values)
+                            .map(|(key, value)| (key.to_string(), value.to_json()))
+                            .collect(),
+                    ),
+                    None => keys
+                        .iter()
+                        .zip(values)
+                        .map(|(key, value)| json!([key.to_json(), value.to_json()]))
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    /// Converts a JSON document to a value, reversing the mapping of [Value::to_json] where possible:
+    /// strings become [Value::BulkString], integers [Value::Integer], other numbers [Value::Double]
+    /// (including the integers that don't fit in an `i64`, which keep all their digits),
+    /// objects with the single key `error` [Value::SimpleError] and every other object a [Value::Map].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::Value;
+    ///
+    /// let json = serde_json::json!({"a": [1, "b"]});
+    /// assert_eq!(
+    ///     Value::from_json(&json),
+    ///     Value::Map(
+    ///         vec![Value::BulkString("a")],
+    ///         vec![Value::Array(vec![Value::Integer(1), Value::BulkString("b")])]
+    ///     )
+    /// );
+    /// ```
+    pub fn from_json(json: &'a serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Boolean(*b),
+            serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => Value::Integer(i),
+                // integers that don't fit in an i64 become doubles, keeping all their digits
+                (None, Some(u)) => Value::Double(u.to_string()),
+                (None, None) => Value::Double(format!("{}", n.as_f64().unwrap_or(f64::NAN))),
+            },
+            serde_json::Value::String(s) => Value::BulkString(s),
+            serde_json::Value::Array(values) => {
+                Value::Array(values.iter().map(Value::from_json).collect())
+            }
+            serde_json::Value::Object(map) => match map.get("error") {
+                Some(serde_json::Value::String(error)) if map.len() == 1 => {
+                    Value::SimpleError(error)
+                }
+                _ => Value::Map(
+                    map.keys().map(|key| Value::BulkString(key)).collect(),
+                    map.values().map(Value::from_json).collect(),
+                ),
+            },
+        }
+    }
+
+    /// Converts the value to JSON, tagging every node with its RESP type so that the conversion can be
+    /// reversed exactly with [Value::from_json_lossless].
+    ///
+    /// Every node is an object with a `type` and (except for `null`) a `value`:
+    ///
+    /// | RESP type                 | `type`            | `value`                                          |
+    /// |---------------------------|-------------------|--------------------------------------------------|
+    /// | [Value::SimpleString]     | `simple_string`   | string                                           |
+    /// | [Value::SimpleError]      | `simple_error`    | string                                           |
+    /// | [Value::Integer]          | `integer`         | number                                           |
+    /// | [Value::BulkString]       | `bulk_string`     | string                                           |
+    /// | [Value::Array]            | `array`           | array of nodes                                   |
+    /// | [Value::Null]             | `null`            |                                                  |
+    /// | [Value::Boolean]          | `boolean`         | boolean                                          |
+    /// | [Value::Double]           | `double`          | string (to preserve `inf` and `nan`)             |
+    /// | [Value::BigNumber]        | `big_number`      | string                                           |
+    /// | [Value::BulkError]        | `bulk_error`      | string                                           |
+    /// | [Value::VerbatimString]   | `verbatim_string` | string (the format is in the `format` field)     |
+    /// | [Value::Map]              | `map`             | array of `[key, value]` pairs of nodes           |
+    /// | [Value::Set]              | `set`             | array of nodes                                   |
+    /// | [Value::Pushes]           | `push`            | array of nodes                                   |
+    /// | [Value::BulkBytes]        | `bulk_bytes`      | array of bytes                                   |
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::parse;
+    ///
+    /// let value = parse("*2\r\n+OK\r\n=8\r\nmkd:# hi\r\n").unwrap();
+    /// assert_eq!(
+    ///     value.to_json_lossless(),
+    ///     serde_json::json!({"type": "array", "value": [
+    ///         {"type": "simple_string", "value": "OK"},
+    ///         {"type": "verbatim_string", "format": "mkd", "value": "# hi"}
+    ///     ]})
+    /// );
+    /// ```
+    pub fn to_json_lossless(&self) -> serde_json::Value {
+        let node = |kind: &str, value: serde_json::Value| json!({ "type": kind, "value": value });
+        let nodes = |values: &mut dyn Iterator<Item = &Value<'a>>| -> serde_json::Value {
+            values.map(Value::to_json_lossless).collect()
+        };
+        match self {
+            Value::SimpleString(s) => node("simple_string", json!(s)),
+            Value::SimpleError(s) => node("simple_error", json!(s)),
+            Value::Integer(i) => node("integer", json!(i)),
+            Value::BulkString(s) => node("bulk_string", json!(s)),
+            Value::Array(values) => node("array", nodes(&mut values.iter())),
+            Value::Null => json!({ "type": "null" }),
+            Value::Boolean(b) => node("boolean", json!(b)),
+            Value::Double(d) => node("double", json!(d)),
+            Value::BigNumber(n) => node("big_number", json!(n)),
+            Value::BulkError(s) => node("bulk_error", json!(s)),
+            Value::VerbatimString(format, s) => {
+                json!({ "type": "verbatim_string", "format": format.to_string(), "value": s })
+            }
+            Value::Map(keys, values) => node(
+                "map",
+                keys.iter()
+                    .zip(values)
+                    .map(|(key, value)| json!([key.to_json_lossless(), value.to_json_lossless()]))
+                    .collect(),
+            ),
+            Value::Set(values) => node("set", nodes(&mut values.iter())),
+            Value::Pushes(values) => node("push", nodes(&mut values.iter())),
+            Value::BulkBytes(bytes) => node("bulk_bytes", json!(bytes)),
+        }
+    }
+
+    /// Converts a JSON document produced by [Value::to_json_lossless] back to a value
+    ///
+    /// The `bulk_bytes` nodes are rejected, since their bytes can't be borrowed from the document.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::{parse, Value};
+    ///
+    /// let value = parse("%1\r\n:1\r\n,inf\r\n").unwrap();
+    /// let json = value.to_json_lossless();
+    /// assert_eq!(Value::from_json_lossless(&json), Ok(value));
+    /// ```
+    pub fn from_json_lossless(json: &'a serde_json::Value) -> Result<Self, FromJsonError> {
+        let kind = json
+            .get("type")
+            .and_then(serde_json::Value::as_str)
+            .ok_or(FromJsonError::InvalidNode("unknown"))?;
+        let value = json.get("value").unwrap_or(&serde_json::Value::Null);
+        let string = |kind: &'static str| value.as_str().ok_or(FromJsonError::InvalidNode(kind));
+        let nodes = |kind: &'static str| -> Result<Vec<Value<'a>>, FromJsonError> {
+            value
+                .as_array()
+                .ok_or(FromJsonError::InvalidNode(kind))?
+                .iter()
+                .map(Value::from_json_lossless)
+                .collect()
+        };
+        Ok(match kind {
+            "simple_string" => Value::SimpleString(string("simple_string")?),
+            "simple_error" => Value::SimpleError(string("simple_error")?),
+            "integer" => Value::Integer(
+                value
+                    .as_i64()
+                    .ok_or(FromJsonError::InvalidNode("integer"))?,
+            ),
+            "bulk_string" => Value::BulkString(string("bulk_string")?),
+            "array" => Value::Array(nodes("array")?),
+            "null" => Value::Null,
+            "boolean" => Value::Boolean(
+                value
+                    .as_bool()
+                    .ok_or(FromJsonError::InvalidNode("boolean"))?,
+            ),
+            "double" => Value::Double(string("double")?.to_string()),
+            "big_number" => Value::BigNumber(string("big_number")?),
+            "bulk_error" => Value::BulkError(string("bulk_error")?),
+            "verbatim_string" => {
+                let format = json
+                    .get("format")
+                    .and_then(serde_json::Value::as_str)
+                    .and_then(VerbatimFormat::from_prefix)
+                    .ok_or(FromJsonError::InvalidNode("verbatim_string"))?;
+                Value::VerbatimString(format, string("verbatim_string")?)
+            }
+            "map" => {
+                let mut keys = Vec::new();
+                let mut values = Vec::new();
+                for pair in value.as_array().ok_or(FromJsonError::InvalidNode("map"))? {
+                    match pair.as_array().map(Vec::as_slice) {
+                        Some([key, value]) => {
+                            keys.push(Value::from_json_lossless(key)?);
+                            values.push(Value::from_json_lossless(value)?);
+                        }
+                        _ => return Err(FromJsonError::InvalidNode("map")),
+                    }
+                }
+                Value::Map(keys, values)
+            }
+            "set" => Value::Set(nodes("set")?.into_iter().collect()),
+            "push" => Value::Pushes(nodes("push")?),
+            "bulk_bytes" => return Err(FromJsonError::InvalidNode("bulk_bytes")),
+            kind => return Err(FromJsonError::UnknownType(kind.to_string())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_to_json() {
+        let value = parse(
+            "*7\r\n+OK\r\n-ERR no\r\n:-3\r\n,1.5\r\n,-inf\r\n(12345678901234567890\r\n#f\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value.to_json(),
+            json!(["OK", {"error": "ERR no"}, -3, 1.5, "-inf", "12345678901234567890", false])
+        );
+
+        let value = parse("%2\r\n+b\r\n:1\r\n+a\r\n:2\r\n").unwrap();
+        assert_eq!(value.to_json(), json!({"b": 1, "a": 2}));
+        // maps with non string or duplicate keys become arrays of pairs
+        let value = parse("%2\r\n:1\r\n+a\r\n:2\r\n+b\r\n").unwrap();
+        assert_eq!(value.to_json(), json!([[1, "a"], [2, "b"]]));
+        let value = parse("%2\r\n+a\r\n:1\r\n+a\r\n:2\r\n").unwrap();
+        assert_eq!(value.to_json(), json!([["a", 1], ["a", 2]]));
+    }
+
+    #[test]
+    fn test_from_json_large_integers() {
+        let json = json!([i64::MAX, i64::MIN, u64::MAX, 1e20]);
+        assert_eq!(
+            Value::from_json(&json),
+            Value::Array(vec![
+                Value::Integer(i64::MAX),
+                Value::Integer(i64::MIN),
+                Value::Double("18446744073709551615".to_string()),
+                Value::Double("100000000000000000000".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = json!([null, true, 1, 1.5, "s", {"error": "ERR"}, {"error": 1}]);
+        assert_eq!(
+            Value::from_json(&json),
+            Value::Array(vec![
+                Value::Null,
+                Value::Boolean(true),
+                Value::Integer(1),
+                Value::Double("1.5".to_string()),
+                Value::BulkString("s"),
+                Value::SimpleError("ERR"),
+                Value::Map(vec![Value::BulkString("error")], vec![Value::Integer(1)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_json_lossless_roundtrip() {
+        let messages = [
+            "*3\r\n+OK\r\n-ERR\r\n:1\r\n",
+            "$5\r\nhello\r\n",
+            "_\r\n",
+            "#t\r\n",
+            ",nan\r\n",
+            "(-123\r\n",
+            "!3\r\nERR\r\n",
+            "=7\r\nabc:xyz\r\n",
+            "%2\r\n:1\r\n*0\r\n$1\r\na\r\n_\r\n",
+            "~2\r\n:1\r\n:2\r\n",
+            ">2\r\n+message\r\n+hi\r\n",
+        ];
+        for message in messages {
+            let value = parse(message).unwrap();
+            let json = value.to_json_lossless();
+            assert_eq!(Value::from_json_lossless(&json), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_from_json_lossless_errors() {
+        assert_eq!(
+            Value::from_json_lossless(&json!({"type": "what"})),
+            Err(FromJsonError::UnknownType("what".to_string()))
+        );
+        assert_eq!(
+            Value::from_json_lossless(&json!({"value": 1})),
+            Err(FromJsonError::InvalidNode("unknown"))
+        );
+        assert_eq!(
+            Value::from_json_lossless(&json!({"type": "integer", "value": "1"})),
+            Err(FromJsonError::InvalidNode("integer"))
+        );
+        assert_eq!(
+            Value::from_json_lossless(&json!({"type": "map", "value": [[{"type": "null"}]]})),
+            Err(FromJsonError::InvalidNode("map"))
+        );
+    }
+}
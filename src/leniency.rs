@@ -0,0 +1,168 @@
+use std::fmt;
+
+/// Deviations from the RESP specification that the parsers can tolerate, to read data sent
+/// by non-compliant peers (e.g. older proxies or embedded servers).
+///
+/// Everything is disabled by default, so the parsers are strict unless a deviation is explicitly allowed.
+/// Every tolerated deviation is recorded as a [Warning] (see [parse_value_with_warnings](crate::parse_value_with_warnings)).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Leniency {
+    /// Accepts lines terminated by a bare `\n` instead of `\r\n`
+    pub bare_lf: bool,
+    /// Accepts the special doubles (`inf`, `-inf` and `nan`) in any case (e.g. `NAN` or `Inf`)
+    pub double_case: bool,
+    /// Accepts booleans in uppercase (`#T` and `#F`)
+    pub boolean_case: bool,
+}
+
+impl Leniency {
+    /// Tolerates all the supported deviations
+    pub fn all() -> Self {
+        Leniency {
+            bare_lf: true,
+            double_case: true,
+            boolean_case: true,
+        }
+    }
+}
+
+/// A deviation from the RESP specification, tolerated because of a [Leniency] option
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Deviation {
+    /// A line terminated by `\n` instead of `\r\n`
+    BareLf,
+    /// A special double (`inf`, `-inf` or `nan`) not in lowercase
+    DoubleCase,
+    /// An uppercase boolean (`#T` or `#F`)
+    BooleanCase,
+}
+
+/// Records a deviation from the RESP specification tolerated while parsing
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Warning {
+    /// The position of the non-compliant bytes in the input
+    pub offset: usize,
+    pub deviation: Deviation,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self.deviation {
+            Deviation::BareLf => "line terminated by a bare LF",
+            Deviation::DoubleCase => "special double not in lowercase",
+            Deviation::BooleanCase => "uppercase boolean",
+        };
+        write!(f, "{} at byte {}", description, self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_value, parse_value_with, parse_value_with_warnings, ParseOptions, Value};
+
+    fn lenient() -> ParseOptions {
+        ParseOptions {
+            leniency: Leniency::all(),
+            ..ParseOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_strict_by_default() {
+        for input in [
+            "+OK\n",
+            ":1\n",
+            "$5\nhello\n",
+            "$5\r\nhello\n",
+            "*1\n:1\r\n",
+            "_\n",
+            "#T\r\n",
+            "#F\r\n",
+            ",NAN\r\n",
+            ",Inf\r\n",
+            ",-INF\r\n",
+            ",infinity\r\n",
+        ] {
+            assert!(parse_value(input).is_err(), "{:?}", input);
+            let (result, warnings) = parse_value_with_warnings(input, &ParseOptions::default());
+            assert!(result.is_err(), "{:?}", input);
+            assert!(warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_bare_lf() {
+        let message = "*3\n$5\nhello\n+OK\r\n%1\n:1\n_\n";
+        let (result, warnings) = parse_value_with_warnings(message, &lenient());
+        assert_eq!(
+            result,
+            Ok((
+                "",
+                Value::Array(vec![
+                    Value::BulkString("hello"),
+                    Value::SimpleString("OK"),
+                    Value::Map(vec![Value::Integer(1)], vec![Value::Null]),
+                ])
+            ))
+        );
+        let offsets: Vec<_> = warnings.iter().map(|warning| warning.offset).collect();
+        assert_eq!(offsets, vec![2, 5, 11, 19, 22, 24]);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.deviation == Deviation::BareLf));
+        assert_eq!(
+            warnings[0].to_string(),
+            "line terminated by a bare LF at byte 2"
+        );
+
+        // a CR must still be followed by a LF
+        assert!(parse_value_with("+O\rK\n", &lenient()).is_err());
+        // the length of bulk strings doesn't change
+        assert!(parse_value_with("$5\nhell\n", &lenient()).is_err());
+    }
+
+    #[test]
+    fn test_double_and_boolean_case() {
+        let message = "*4\r\n,NAN\r\n,-Inf\r\n#T\r\n#f\r\n";
+        let (result, warnings) = parse_value_with_warnings(message, &lenient());
+        assert_eq!(
+            result.unwrap().1,
+            Value::Array(vec![
+                Value::Double("NaN".to_string()),
+                Value::Double("-inf".to_string()),
+                Value::Boolean(true),
+                Value::Boolean(false),
+            ])
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                Warning {
+                    offset: 5,
+                    deviation: Deviation::DoubleCase
+                },
+                Warning {
+                    offset: 11,
+                    deviation: Deviation::DoubleCase
+                },
+                Warning {
+                    offset: 18,
+                    deviation: Deviation::BooleanCase
+                },
+            ]
+        );
+
+        // only the enabled deviations are tolerated
+        let options = ParseOptions {
+            leniency: Leniency {
+                boolean_case: true,
+                ..Leniency::default()
+            },
+            ..ParseOptions::default()
+        };
+        assert!(parse_value_with("#T\r\n", &options).is_ok());
+        assert!(parse_value_with(",NAN\r\n", &options).is_err());
+        assert!(parse_value_with("#T\n", &options).is_err());
+    }
+}
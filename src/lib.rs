@@ -23,17 +23,53 @@
 //! ```
 
 use nom::{
-    branch::alt,
-    bytes::complete::{tag, take, take_while},
-    character::complete::{digit1, i64, one_of, u32},
-    combinator::{eof, map, opt},
+    bytes::complete::tag,
+    character::complete::{digit1, one_of},
+    combinator::{eof, map_opt, opt},
+    error::{Error, ErrorKind},
     multi::count,
-    number::complete::double,
+    number::complete::recognize_float,
     sequence::terminated,
     IResult,
 };
+use std::cell::{Cell, RefCell};
 
+pub mod aof;
+pub mod client;
+mod command;
+mod decoder;
+mod display;
+mod frame;
+mod from_resp;
+mod hello;
+#[cfg(feature = "json")]
+mod json;
+mod leniency;
+mod owned;
+mod push;
+pub mod replies;
+pub mod server;
+mod span;
+#[cfg(any(test, feature = "proptest"))]
+mod strategy;
+mod to_resp;
 mod value;
+pub use command::*;
+pub use decoder::*;
+pub use frame::*;
+pub use from_resp::*;
+pub use hello::*;
+#[cfg(feature = "json")]
+pub use json::*;
+pub use leniency::*;
+pub use owned::*;
+pub use push::*;
+pub use span::*;
+#[cfg(feature = "proptest")]
+pub use strategy::*;
+#[cfg(feature = "derive")]
+pub use tinyresp_derive::FromResp;
+pub use to_resp::*;
 pub use value::*;
 
 /// Parses a complete RESP message using an incremental parsing approach.
@@ -45,6 +81,7 @@ pub use value::*;
 ///
 /// This function is exposed only for advanced use cases, for instance if you need to combine this parser with other nom parsers.
 /// Most of the times you will want to use the [`parse`] function for one-shot parsing or the [`parse_value`] function for incremental parsing.
+#[allow(mismatched_lifetime_syntaxes)]
 pub fn parse_message(input: &str) -> IResult<&str, Value> {
     let (input, value) = terminated(parse_value, eof)(input)?;
     Ok((input, value))
@@ -54,6 +91,7 @@ pub fn parse_message(input: &str) -> IResult<&str, Value> {
 /// It internally uses [`parse_message`] and returns the parsed value directly (or an error).
 /// This function will return an error if you have any leftover input because [`parse_message`] makes sure you consume
 /// all the input. If you want to use an incremental approach, you are recommended to use [`parse_value`] instead.
+#[allow(mismatched_lifetime_syntaxes)]
 pub fn parse(input: &str) -> Result<Value, String> {
     let (_, value) = parse_message(input).map_err(|e| format!("{}", e))?;
     Ok(value)
@@ -77,109 +115,421 @@ pub fn parse(input: &str) -> Result<Value, String> {
 ///     Value::BulkString("world")
 /// ]));
 /// ```
+#[allow(mismatched_lifetime_syntaxes)]
 pub fn parse_value(input: &str) -> IResult<&str, Value> {
-    alt((
-        parse_simple_string,
-        parse_simple_error,
-        parse_integer,
-        parse_bulk_string,
-        parse_array,
-        parse_null,
-        parse_bool,
-        parse_double,
-        parse_bignumber,
-        parse_bulk_error,
-        parse_verbatim_string,
-        parse_map,
-        parse_set,
-        parse_pushes,
-    ))(input)
-}
-
-fn values_sequence(input: &str, multiplier: usize) -> IResult<&str, Vec<Value>> {
-    let (input, length) = terminated(u32, crlf)(input)?;
-    let (input, values) = count(parse_value, length as usize * multiplier)(input)?;
-    Ok((input, values))
+    parse_value_with(input, &ParseOptions::default())
+}
+
+/// Options to customize the behaviour of [parse_value_with]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ParseOptions {
+    /// Only accepts length headers in their canonical form, rejecting a leading `+`,
+    /// leading zeros and `-0` (e.g. `$+5`, `$05` or `*-0`).
+    /// By default these headers are accepted, like they would be by a generic integer parser.
+    pub strict_lengths: bool,
+    /// The deviations from the RESP specification to tolerate (none by default)
+    pub leniency: Leniency,
+}
+
+/// Parses a RESP value like [parse_value] does, using the given options.
+///
+/// Independently of the options, `-1` is accepted as a length only by bulk strings (`$-1`) and
+/// arrays (`*-1`), which are parsed as [Value::Null], and lengths that don't fit in an `i64`
+/// (or in a `usize` on 32-bit targets) are rejected.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{parse_value_with, ParseOptions, Value};
+///
+/// let strict = ParseOptions { strict_lengths: true, ..ParseOptions::default() };
+/// assert_eq!(parse_value_with("$5\r\nhello\r\n", &strict).unwrap().1, Value::BulkString("hello"));
+/// assert_eq!(parse_value_with("*-1\r\n", &strict).unwrap().1, Value::Null);
+/// assert!(parse_value_with("$05\r\nhello\r\n", &strict).is_err());
+/// assert!(parse_value_with("$05\r\nhello\r\n", &ParseOptions::default()).is_ok());
+/// ```
+pub fn parse_value_with<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Value<'a>> {
+    value(input, &Context::new(input, options))
+}
+
+/// Parses a RESP value like [parse_value_with] does, also returning the deviations from the
+/// RESP specification that were tolerated because of the [Leniency] options.
+///
+/// The warnings are returned even if parsing fails.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{parse_value_with_warnings, Deviation, Leniency, ParseOptions, Value};
+///
+/// let options = ParseOptions { leniency: Leniency::all(), ..ParseOptions::default() };
+/// let (result, warnings) = parse_value_with_warnings("*2\n#T\r\n:1\r\n", &options);
+/// assert_eq!(
+///     result.unwrap().1,
+///     Value::Array(vec![Value::Boolean(true), Value::Integer(1)])
+/// );
+/// assert_eq!(warnings.len(), 2);
+/// assert_eq!(warnings[0].deviation, Deviation::BareLf);
+/// assert_eq!(warnings[1].to_string(), "uppercase boolean at byte 4");
+/// ```
+pub fn parse_value_with_warnings<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> (IResult<&'a str, Value<'a>>, Vec<Warning>) {
+    let ctx = Context::new(input, options);
+    let result = value(input, &ctx);
+    (result, ctx.warnings.into_inner())
+}
+
+/// The state shared by the parsers while parsing a value
+pub(crate) struct Context<'o> {
+    options: &'o ParseOptions,
+    /// The length of the whole input, used to compute the offset of the warnings
+    input_len: usize,
+    warnings: RefCell<Vec<Warning>>,
+}
+
+impl<'o> Context<'o> {
+    pub(crate) fn new(input: &str, options: &'o ParseOptions) -> Self {
+        Context {
+            options,
+            input_len: input.len(),
+            warnings: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Records a deviation found at the start of the given input
+    fn warn(&self, input: &str, deviation: Deviation) {
+        self.warnings.borrow_mut().push(Warning {
+            offset: self.input_len - input.len(),
+            deviation,
+        });
+    }
+
+    /// Parses a line terminator, which is a CRLF unless bare LFs are tolerated
+    fn newline<'a>(&self, input: &'a str) -> IResult<&'a str, &'a str> {
+        if let Some(rest) = input.strip_prefix("\r\n") {
+            return Ok((rest, &input[..2]));
+        }
+        if self.options.leniency.bare_lf && input.starts_with('\n') {
+            self.warn(input, Deviation::BareLf);
+            return Ok((&input[1..], &input[..1]));
+        }
+        crlf(input)
+    }
+}
+
+fn value<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
+    // the type byte selects the parser, so there's no need to try all of them in turn
+    match input.as_bytes().first() {
+        Some(b'+') => parse_simple_string(input, ctx),
+        Some(b'-') => parse_simple_error(input, ctx),
+        Some(b':') => parse_integer(input, ctx),
+        Some(b'$') => parse_bulk_string(input, ctx),
+        Some(b'*') => parse_array(input, ctx),
+        Some(b'_') => parse_null(input, ctx),
+        Some(b'#') => parse_bool(input, ctx),
+        Some(b',') => parse_double(input, ctx),
+        Some(b'(') => parse_bignumber(input, ctx),
+        Some(b'!') => parse_bulk_error(input, ctx),
+        Some(b'=') => parse_verbatim_string(input, ctx),
+        Some(b'%') => parse_map(input, ctx),
+        Some(b'~') => parse_set(input, ctx),
+        Some(b'>') => parse_pushes(input, ctx),
+        _ => Err(nom::Err::Error(Error::new(input, ErrorKind::Tag))),
+    }
+}
+
+/// The maximum nesting depth of aggregate types (arrays, maps, sets and pushes) accepted by the parsers.
+/// Deeper values are rejected with a failure, as parsing them could overflow the stack.
+pub const MAX_DEPTH: usize = 128;
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Tracks the nesting depth of the aggregate being parsed, the depth is decreased when the guard is dropped
+struct DepthGuard;
+
+impl DepthGuard {
+    fn enter(input: &str) -> Result<Self, nom::Err<Error<&str>>> {
+        DEPTH.with(|depth| {
+            if depth.get() >= MAX_DEPTH {
+                return Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge)));
+            }
+            depth.set(depth.get() + 1);
+            Ok(DepthGuard)
+        })
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Parses the length header and the values of an aggregate type, returning `None` for the `-1` length
+/// (only allowed if `nullable`)
+fn values_sequence<'a>(
+    input: &'a str,
+    multiplier: usize,
+    nullable: bool,
+    ctx: &Context,
+) -> IResult<&'a str, Option<Vec<Value<'a>>>> {
+    let _guard = DepthGuard::enter(input)?;
+    let (input, length) = length_header(input, nullable, ctx)?;
+    let Length::Count(length) = length else {
+        return Ok((input, None));
+    };
+    let length = length
+        .checked_mul(multiplier)
+        .ok_or_else(|| nom::Err::Error(Error::new(input, ErrorKind::TooLarge)))?;
+    let (input, values) = count(|input| value(input, ctx), length)(input)?;
+    Ok((input, Some(values)))
 }
 
 fn crlf(input: &str) -> IResult<&str, &str> {
     tag("\r\n")(input)
 }
 
-fn parse_simple_string_raw(input: &str) -> IResult<&str, &str> {
-    terminated(take_while(|c| c != '\r' && c != '\n'), crlf)(input)
+/// The value of the length header of a bulk or aggregate type
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Length {
+    /// The `-1` length, representing a null value for the types that support it
+    Null,
+    Count(usize),
+}
+
+/// Parses the content of a length header (without the type byte and the CRLF).
+///
+/// Returns `None` if the length is invalid: `-1` is accepted only if `nullable` and in `strict` mode
+/// a leading `+`, leading zeros and `-0` are rejected. Lengths that don't fit in an `i64`
+/// (or in a `usize` on 32-bit targets) are always rejected.
+/// This is shared by the parsers and [frame_len], so that they agree on what's a valid length.
+pub(crate) fn parse_length(line: &[u8], nullable: bool, strict: bool) -> Option<Length> {
+    let (negative, digits) = match line.split_first() {
+        Some((b'-', digits)) => (true, digits),
+        Some((b'+', digits)) if !strict => (false, digits),
+        _ => (false, line),
+    };
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    if strict && digits.len() > 1 && digits[0] == b'0' {
+        return None;
+    }
+    let mut value: i64 = 0;
+    for digit in digits {
+        value = value
+            .checked_mul(10)?
+            .checked_add(i64::from(digit - b'0'))?;
+    }
+    match (negative, value) {
+        (true, 1) if nullable => Some(Length::Null),
+        (true, 0) if !strict => Some(Length::Count(0)),
+        (true, _) => None,
+        (false, value) => usize::try_from(value).ok().map(Length::Count),
+    }
+}
+
+/// Parses a length header (including the line terminator) with [parse_length]
+pub(crate) fn length_header<'a>(
+    input: &'a str,
+    nullable: bool,
+    ctx: &Context,
+) -> IResult<&'a str, Length> {
+    let end = line_end(input.as_bytes()).unwrap_or(input.len());
+    let length = parse_length(
+        &input.as_bytes()[..end],
+        nullable,
+        ctx.options.strict_lengths,
+    )
+    .ok_or_else(|| nom::Err::Error(Error::new(input, ErrorKind::Digit)))?;
+    let (input, _) = ctx.newline(&input[end..])?;
+    Ok((input, length))
+}
+
+/// Checks if the line is an integer that the parser accepts (it must fit in an `i64`).
+/// This is shared by the parser and [frame_len], so that they agree on what's a valid integer.
+pub(crate) fn is_i64(line: &str) -> bool {
+    matches!(i64(line), Ok(("", _)))
+}
+
+/// Parses a signed decimal number (with an optional `+` or `-` sign) that fits in an `i64`.
+/// This is a faster replacement of nom's `i64` parser, which fails in the same cases.
+fn i64(input: &str) -> IResult<&str, i64> {
+    let (negative, unsigned) = match input.as_bytes().first() {
+        Some(b'-') => (true, &input[1..]),
+        Some(b'+') => (false, &input[1..]),
+        _ => (false, input),
+    };
+    let digits = unsigned.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return Err(nom::Err::Error(Error::new(input, ErrorKind::Digit)));
+    }
+    let mut value: i64 = 0;
+    for byte in unsigned[..digits].bytes() {
+        let digit = i64::from(byte - b'0');
+        // negative numbers are accumulated as such, so that `i64::MIN` doesn't overflow
+        value = value
+            .checked_mul(10)
+            .and_then(|value| {
+                if negative {
+                    value.checked_sub(digit)
+                } else {
+                    value.checked_add(digit)
+                }
+            })
+            .ok_or_else(|| nom::Err::Error(Error::new(input, ErrorKind::Digit)))?;
+    }
+    Ok((&unsigned[digits..], value))
+}
+
+/// Finds the first `\r` or `\n` in the input.
+/// Short lines (the most common case) are scanned directly, longer ones are searched with `memchr`.
+fn line_end(input: &[u8]) -> Option<usize> {
+    const SHORT_LINE: usize = 16;
+    let head = &input[..input.len().min(SHORT_LINE)];
+    match head.iter().position(|&b| b == b'\r' || b == b'\n') {
+        Some(end) => Some(end),
+        None if input.len() > SHORT_LINE => {
+            memchr::memchr2(b'\r', b'\n', &input[SHORT_LINE..]).map(|end| SHORT_LINE + end)
+        }
+        None => None,
+    }
+}
+
+/// Takes the given number of bytes from the input.
+/// Unlike nom's `take`, which counts characters, this matches the lengths used by RESP.
+/// It fails if the input is too short or if the length ends in the middle of a UTF-8 character.
+fn take_bytes(length: usize) -> impl Fn(&str) -> IResult<&str, &str> {
+    move |input: &str| {
+        if input.len() < length || !input.is_char_boundary(length) {
+            return Err(nom::Err::Error(Error::new(input, ErrorKind::Eof)));
+        }
+        let (value, input) = input.split_at(length);
+        Ok((input, value))
+    }
+}
+
+fn parse_simple_string_raw<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, &'a str> {
+    let end = line_end(input.as_bytes()).unwrap_or(input.len());
+    let (value, input) = input.split_at(end);
+    let (input, _) = ctx.newline(input)?;
+    Ok((input, value))
 }
 
-fn parse_simple_string(input: &str) -> IResult<&str, Value> {
+fn parse_simple_string<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("+")(input)?;
-    let (input, value) = parse_simple_string_raw(input)?;
+    let (input, value) = parse_simple_string_raw(input, ctx)?;
     Ok((input, Value::SimpleString(value)))
 }
 
-fn parse_simple_error(input: &str) -> IResult<&str, Value> {
+fn parse_simple_error<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("-")(input)?;
-    let (input, value) = parse_simple_string_raw(input)?;
+    let (input, value) = parse_simple_string_raw(input, ctx)?;
     Ok((input, Value::SimpleError(value)))
 }
 
-fn parse_integer(input: &str) -> IResult<&str, Value> {
+fn parse_integer<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag(":")(input)?;
-    let (input, value) = terminated(i64, crlf)(input)?;
+    let (input, value) = terminated(i64, |input| ctx.newline(input))(input)?;
     Ok((input, Value::Integer(value)))
 }
 
-fn parse_bulk_string_raw(input: &str) -> IResult<&str, &str> {
-    let (input, length) = terminated(u32, crlf)(input)?;
-    let (input, value) = terminated(take(length as usize), crlf)(input)?;
-    Ok((input, value))
+fn parse_bulk_string_raw<'a>(
+    input: &'a str,
+    nullable: bool,
+    ctx: &Context,
+) -> IResult<&'a str, Option<&'a str>> {
+    let (input, length) = length_header(input, nullable, ctx)?;
+    let Length::Count(length) = length else {
+        return Ok((input, None));
+    };
+    let (input, value) = terminated(take_bytes(length), |input| ctx.newline(input))(input)?;
+    Ok((input, Some(value)))
 }
 
-fn parse_bulk_string(input: &str) -> IResult<&str, Value> {
+fn parse_bulk_string<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("$")(input)?;
-    let (input, value) = parse_bulk_string_raw(input)?;
-    Ok((input, Value::BulkString(value)))
+    let (input, value) = parse_bulk_string_raw(input, true, ctx)?;
+    Ok((input, value.map_or(Value::Null, Value::BulkString)))
 }
 
-fn parse_bulk_error(input: &str) -> IResult<&str, Value> {
+fn parse_bulk_error<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("!")(input)?;
-    let (input, value) = parse_bulk_string_raw(input)?;
-    Ok((input, Value::BulkError(value)))
+    let (input, value) = parse_bulk_string_raw(input, false, ctx)?;
+    Ok((input, value.map_or(Value::Null, Value::BulkError)))
 }
 
-fn parse_array(input: &str) -> IResult<&str, Value> {
+fn parse_array<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("*")(input)?;
-    let (input, values) = values_sequence(input, 1)?;
-    Ok((input, Value::Array(values)))
+    // null arrays (`*-1`) are the only nullable aggregates
+    let (input, values) = values_sequence(input, 1, true, ctx)?;
+    Ok((input, values.map_or(Value::Null, Value::Array)))
 }
 
-fn parse_null(input: &str) -> IResult<&str, Value> {
-    let (input, _) = alt((tag("$-1\r\n"), tag("*-1\r\n"), tag("_\r\n")))(input)?;
+fn parse_null<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
+    let (input, _) = tag("_")(input)?;
+    let (input, _) = ctx.newline(input)?;
     Ok((input, Value::Null))
 }
 
-fn parse_bool(input: &str) -> IResult<&str, Value> {
+fn parse_bool<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("#")(input)?;
-    let (input, ch) = terminated(one_of("tf"), crlf)(input)?;
-    let value = match ch {
-        't' => true,
-        'f' => false,
-        _ => unreachable!(),
-    };
-    Ok((input, Value::Boolean(value)))
+    let (rest, ch) = one_of("tfTF")(input)?;
+    if ch.is_ascii_uppercase() {
+        if !ctx.options.leniency.boolean_case {
+            return Err(nom::Err::Error(Error::new(input, ErrorKind::OneOf)));
+        }
+        ctx.warn(input, Deviation::BooleanCase);
+    }
+    let (input, _) = ctx.newline(rest)?;
+    Ok((input, Value::Boolean(ch.eq_ignore_ascii_case(&'t'))))
 }
 
-fn parse_double(input: &str) -> IResult<&str, Value> {
+/// Parses the special doubles (`inf`, `+inf`, `-inf` and `nan`) in any case
+fn special_double(line: &str) -> Option<f64> {
+    if line.eq_ignore_ascii_case("inf") || line.eq_ignore_ascii_case("+inf") {
+        Some(f64::INFINITY)
+    } else if line.eq_ignore_ascii_case("-inf") {
+        Some(f64::NEG_INFINITY)
+    } else if line.eq_ignore_ascii_case("nan") {
+        Some(f64::NAN)
+    } else {
+        None
+    }
+}
+
+/// Parses the content of a double as allowed by the specification (the special values are lowercase).
+/// This is shared by the parser and [frame_len], so that they agree on what's a valid double.
+pub(crate) fn strict_double(line: &str) -> Option<f64> {
+    match line {
+        "inf" | "+inf" => Some(f64::INFINITY),
+        "-inf" => Some(f64::NEG_INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => match recognize_float::<_, ()>(line) {
+            Ok(("", _)) => line.parse::<f64>().ok(),
+            _ => None,
+        },
+    }
+}
+
+fn parse_double<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag(",")(input)?;
-    let (input, value) = terminated(
-        alt((
-            // map(tag("inf"), || f64::INFINITY), // NOTE: This is already supported by nom
-            map(tag("+inf"), |_| f64::INFINITY),
-            map(tag("-inf"), |_| f64::NEG_INFINITY),
-            // map(tag("nan"), || f64::NAN), // NOTE: This is already supported by nom
-            double,
-        )),
-        crlf,
-    )(input)?;
+    let end = line_end(input.as_bytes()).unwrap_or(input.len());
+    let (line, rest) = input.split_at(end);
+    let invalid = || nom::Err::Error(Error::new(input, ErrorKind::Float));
+    let value = match (strict_double(line), special_double(line)) {
+        (Some(value), _) => value,
+        (None, Some(value)) if ctx.options.leniency.double_case => {
+            ctx.warn(input, Deviation::DoubleCase);
+            value
+        }
+        _ => return Err(invalid()),
+    };
+    let (input, _) = ctx.newline(rest)?;
 
     let val_as_string = format!("{}", value);
     Ok((input, Value::Double(val_as_string)))
@@ -189,53 +539,70 @@ fn plus_or_minus(input: &str) -> IResult<&str, char> {
     one_of("+-")(input)
 }
 
-fn parse_bignumber(input: &str) -> IResult<&str, Value> {
+fn parse_bignumber<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let original_input = input;
     let (input, _) = tag("(")(input)?;
     let (input, sign) = opt(plus_or_minus)(input)?;
-    let (input, digits) = terminated(digit1, crlf)(input)?;
+    let (input, digits) = terminated(digit1, |input| ctx.newline(input))(input)?;
     let num_slice = &original_input[1..digits.len() + if sign.is_some() { 2 } else { 1 }];
     Ok((input, Value::BigNumber(num_slice)))
 }
 
-fn parse_verbatim_string(input: &str) -> IResult<&str, Value> {
+fn parse_verbatim_string<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("=")(input)?;
-    let (input, length) = terminated(u32, crlf)(input)?;
-    let (input, encoding) = terminated(take(3usize), tag(":"))(input)?;
-    let (input, value) = terminated(take(length as usize - 4usize), crlf)(input)?;
-    Ok((input, Value::VerbatimString(encoding, value)))
+    // the length includes the 3 bytes of the format and the `:` separator
+    let (input, length) = map_opt(
+        |input| length_header(input, false, ctx),
+        |length| match length {
+            Length::Count(length) if length >= 4 => Some(length),
+            _ => None,
+        },
+    )(input)?;
+    let (input, format) = terminated(
+        map_opt(take_bytes(3), VerbatimFormat::from_prefix),
+        tag(":"),
+    )(input)?;
+    let (input, value) = terminated(take_bytes(length - 4), |input| ctx.newline(input))(input)?;
+    Ok((input, Value::VerbatimString(format, value)))
 }
 
-fn parse_map(input: &str) -> IResult<&str, Value> {
+fn parse_map<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("%")(input)?;
-    let (input, keys_and_values) = values_sequence(input, 2)?;
-
-    let (keys, values) = keys_and_values.into_iter().enumerate().fold(
-        (Vec::new(), Vec::new()),
-        |(mut keys, mut values), (idx, val)| {
-            if idx % 2 == 0 {
-                keys.push(val);
-            } else {
-                values.push(val);
-            }
+    let (input, keys_and_values) = values_sequence(input, 2, false, ctx)?;
 
-            (keys, values)
-        },
-    );
+    let (keys, values) = keys_and_values
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .fold(
+            (Vec::new(), Vec::new()),
+            |(mut keys, mut values), (idx, val)| {
+                if idx % 2 == 0 {
+                    keys.push(val);
+                } else {
+                    values.push(val);
+                }
+
+                (keys, values)
+            },
+        );
 
     Ok((input, Value::Map(keys, values)))
 }
 
-fn parse_set(input: &str) -> IResult<&str, Value> {
+fn parse_set<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag("~")(input)?;
-    let (input, values) = values_sequence(input, 1)?;
-    Ok((input, Value::Set(values.into_iter().collect())))
+    let (input, values) = values_sequence(input, 1, false, ctx)?;
+    Ok((
+        input,
+        Value::Set(values.unwrap_or_default().into_iter().collect()),
+    ))
 }
 
-fn parse_pushes(input: &str) -> IResult<&str, Value> {
+fn parse_pushes<'a>(input: &'a str, ctx: &Context) -> IResult<&'a str, Value<'a>> {
     let (input, _) = tag(">")(input)?;
-    let (input, values) = values_sequence(input, 1)?;
-    Ok((input, Value::Pushes(values)))
+    let (input, values) = values_sequence(input, 1, false, ctx)?;
+    Ok((input, Value::Pushes(values.unwrap_or_default())))
 }
 
 #[cfg(test)]
@@ -283,6 +650,97 @@ mod tests {
         assert!(parse_message(":1000\r\nTHIS_SHOULD_NOT_BE_HERE").is_err());
     }
 
+    #[test]
+    fn test_parse_numbers_like_nom() {
+        use nom::character::complete as nom_parsers;
+
+        for input in [
+            "0",
+            "-0",
+            "+12",
+            "-12\r\n",
+            "+",
+            "--1",
+            "9223372036854775807",
+            "9223372036854775808",
+            "-9223372036854775808",
+            "-9223372036854775809",
+        ] {
+            assert_eq!(i64(input), nom_parsers::i64(input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_length() {
+        for strict in [false, true] {
+            assert_eq!(parse_length(b"0", false, strict), Some(Length::Count(0)));
+            assert_eq!(parse_length(b"42", false, strict), Some(Length::Count(42)));
+            assert_eq!(parse_length(b"-1", true, strict), Some(Length::Null));
+            assert_eq!(parse_length(b"-1", false, strict), None);
+            assert_eq!(parse_length(b"-2", true, strict), None);
+            assert_eq!(parse_length(b"", true, strict), None);
+            assert_eq!(parse_length(b"-", true, strict), None);
+            assert_eq!(parse_length(b"1x", false, strict), None);
+            assert_eq!(parse_length(b" 1", false, strict), None);
+            assert_eq!(parse_length(b"++1", false, strict), None);
+            assert_eq!(
+                parse_length(b"9223372036854775807", false, strict),
+                Some(Length::Count(i64::MAX as usize))
+            );
+            assert_eq!(parse_length(b"9223372036854775808", false, strict), None);
+        }
+        assert_eq!(parse_length(b"+5", false, false), Some(Length::Count(5)));
+        assert_eq!(parse_length(b"05", false, false), Some(Length::Count(5)));
+        assert_eq!(parse_length(b"-0", false, false), Some(Length::Count(0)));
+        assert_eq!(parse_length(b"-01", true, false), Some(Length::Null));
+        assert_eq!(parse_length(b"+5", false, true), None);
+        assert_eq!(parse_length(b"05", false, true), None);
+        assert_eq!(parse_length(b"-0", false, true), None);
+        assert_eq!(parse_length(b"-01", true, true), None);
+    }
+
+    #[test]
+    fn test_length_headers() {
+        let strict = ParseOptions {
+            strict_lengths: true,
+            ..ParseOptions::default()
+        };
+        for options in [ParseOptions::default(), strict] {
+            let parse = |input| parse_value_with(input, &options);
+            assert_eq!(parse("$-1\r\n"), Ok(("", Value::Null)));
+            assert_eq!(parse("*-1\r\n"), Ok(("", Value::Null)));
+            assert_eq!(
+                parse("*2\r\n*-1\r\n$-1\r\n"),
+                Ok(("", Value::Array(vec![Value::Null, Value::Null])))
+            );
+            for input in ["!-1\r\n", "=-1\r\n", "%-1\r\n", "~-1\r\n", ">-1\r\n"] {
+                assert!(parse(input).is_err(), "{:?}", input);
+            }
+            // longer than `u32::MAX`, the length is valid but the input is incomplete
+            assert!(parse("$4294967296\r\nhello\r\n").is_err());
+            assert!(parse("*99999999999999999999\r\n").is_err());
+        }
+
+        let lenient = ParseOptions::default();
+        assert_eq!(
+            parse_value_with("$+5\r\nhello\r\n", &lenient),
+            Ok(("", Value::BulkString("hello")))
+        );
+        assert_eq!(
+            parse_value_with("*02\r\n:1\r\n:2\r\n", &lenient),
+            Ok(("", Value::Array(vec![Value::Integer(1), Value::Integer(2)])))
+        );
+        for input in [
+            "$+5\r\nhello\r\n",
+            "$05\r\nhello\r\n",
+            "*-0\r\n",
+            "=09\r\ntxt:hello\r\n",
+        ] {
+            assert!(parse_value(input).is_ok(), "{:?}", input);
+            assert!(parse_value_with(input, &strict).is_err(), "{:?}", input);
+        }
+    }
+
     #[test]
     fn test_parse_bulk_string() {
         assert_eq!(
@@ -459,17 +917,38 @@ mod tests {
     fn test_verbatim_string() {
         assert_eq!(
             parse_message("=15\r\ntxt:Some string\r\n"),
-            Ok(("", Value::VerbatimString("txt", "Some string")))
+            Ok((
+                "",
+                Value::VerbatimString(VerbatimFormat::Txt, "Some string")
+            ))
         );
         assert_eq!(
             parse_message("=5\r\ntxt:1\r\n"),
-            Ok(("", Value::VerbatimString("txt", "1")))
+            Ok(("", Value::VerbatimString(VerbatimFormat::Txt, "1")))
+        );
+        assert_eq!(
+            parse_message("=5\r\nmkd:#\r\n"),
+            Ok(("", Value::VerbatimString(VerbatimFormat::Mkd, "#")))
         );
         assert_eq!(
             parse_message("=5\r\nraw:1\r\n"),
-            Ok(("", Value::VerbatimString("raw", "1")))
+            Ok((
+                "",
+                Value::VerbatimString(VerbatimFormat::from_prefix("raw").unwrap(), "1")
+            ))
+        );
+        assert_eq!(
+            parse_message("=4\r\ntxt:\r\n"),
+            Ok(("", Value::VerbatimString(VerbatimFormat::Txt, "")))
         );
         assert!(parse_message("=5\r\nraw:1\r\nTHIS_SHOULD_NOT_BE_HERE").is_err());
+        // the length must account for the format prefix
+        assert!(parse_message("=2\r\ntx\r\n").is_err());
+        assert!(parse_message("=0\r\n\r\n").is_err());
+        // the format must be made of 3 printable ASCII characters
+        assert!(parse_message("=5\r\nt\rt:1\r\n").is_err());
+        assert!(parse_message("=5\r\nt t:1\r\n").is_err());
+        assert!(parse_message("=6\r\nèxt:1\r\n").is_err());
     }
 
     #[test]
@@ -516,6 +995,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fuzz_regressions() {
+        // lengths are in bytes, not in characters
+        assert_eq!(
+            parse_message("$2\r\n\u{e9}\r\n"),
+            Ok(("", Value::BulkString("\u{e9}")))
+        );
+        assert!(parse_message("$1\r\n\u{e9}\r\n").is_err());
+        let message = "!21\r\nW\u{127}\u{7d6}nvoynt\u{127}\u{7d6}nvoyntaxax\r\n";
+        assert_eq!(
+            frame_len(message.as_bytes()),
+            Err(ParseError::ExpectedCrlf { offset: 26 })
+        );
+        assert!(parse_value(message).is_err());
+        assert_eq!(
+            parse_message("=8\r\ntxt:\u{e9}\u{e9}\r\n"),
+            Ok((
+                "",
+                Value::VerbatimString(VerbatimFormat::Txt, "\u{e9}\u{e9}")
+            ))
+        );
+
+        // verbatim strings shorter than the format and the separator
+        assert!(parse_message("=3\r\ntxt\r\n").is_err());
+
+        // huge lengths from untrusted input must not be trusted to allocate
+        assert!(parse_value("*4294967295\r\n:1\r\n").is_err());
+        assert!(parse_value("%4294967295\r\n").is_err());
+        assert!(parse_value_spanned("~4294967295\r\n:1\r\n").is_err());
+
+        // deeply nested values must not overflow the stack
+        let nested = |depth| format!("{}:1\r\n", "*1\r\n".repeat(depth));
+        assert!(parse_message(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse_value_spanned(&nested(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            parse_message(&nested(MAX_DEPTH + 1)),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(parse_message(&nested(100_000)).is_err());
+        assert!(parse_value_spanned(&nested(100_000)).is_err());
+        // the depth is restored after a failure
+        assert!(parse_message(&nested(MAX_DEPTH)).is_ok());
+    }
+
     #[test]
     fn test_parse() {
         let message = "*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n";
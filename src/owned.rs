@@ -0,0 +1,137 @@
+use crate::{Protocol, StreamedBulk, ToResp, Value, VerbatimFormat};
+use std::collections::BTreeSet;
+
+/// Represents a RESP value that owns its data.
+///
+/// This is useful when a value needs to outlive the buffer it was parsed from
+/// (e.g. when it's received from a connection). It mirrors the variants of [Value]
+/// and can be converted from and to it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[non_exhaustive]
+pub enum OwnedValue {
+    SimpleString(String),
+    SimpleError(String),
+    Integer(i64),
+    BulkString(String),
+    Array(Vec<OwnedValue>),
+    Null,
+    Boolean(bool),
+    Double(String),
+    BigNumber(String),
+    BulkError(String),
+    /// Verbatim strings are represented as a tuple containing the format of the string and its content
+    VerbatimString(VerbatimFormat, String),
+    /// Maps are represented as a tuple of two vectors, the first one contains the keys and the second one the values
+    Map(Vec<OwnedValue>, Vec<OwnedValue>),
+    Set(BTreeSet<OwnedValue>),
+    Pushes(Vec<OwnedValue>),
+    /// A bulk string whose content is not valid UTF-8 (see [Value::BulkBytes])
+    BulkBytes(Vec<u8>),
+    /// A bulk string whose content was written to the sink of a [Decoder](crate::Decoder) instead of
+    /// being kept in memory (see [Decoder::with_sink](crate::Decoder::with_sink)).
+    ///
+    /// It has no [Value] counterpart, since the content is only available in the sink:
+    /// [OwnedValue::as_value] turns it into a [Value::Null] (so it's also encoded and displayed as a null).
+    Streamed(StreamedBulk),
+}
+
+impl OwnedValue {
+    /// Returns a [Value] borrowing the data of the current value,
+    /// so that all the helper methods of [Value] can be used.
+    pub fn as_value(&self) -> Value<'_> {
+        match self {
+            OwnedValue::SimpleString(s) => Value::SimpleString(s),
+            OwnedValue::SimpleError(s) => Value::SimpleError(s),
+            OwnedValue::Integer(i) => Value::Integer(*i),
+            OwnedValue::BulkString(s) => Value::BulkString(s),
+            OwnedValue::Array(values) => Value::Array(values.iter().map(Self::as_value).collect()),
+            OwnedValue::Null => Value::Null,
+            OwnedValue::Boolean(b) => Value::Boolean(*b),
+            OwnedValue::Double(s) => Value::Double(s.clone()),
+            OwnedValue::BigNumber(s) => Value::BigNumber(s),
+            OwnedValue::BulkError(s) => Value::BulkError(s),
+            OwnedValue::VerbatimString(format, s) => Value::VerbatimString(*format, s),
+            OwnedValue::Map(keys, values) => Value::Map(
+                keys.iter().map(Self::as_value).collect(),
+                values.iter().map(Self::as_value).collect(),
+            ),
+            OwnedValue::Set(values) => Value::Set(values.iter().map(Self::as_value).collect()),
+            OwnedValue::Pushes(values) => {
+                Value::Pushes(values.iter().map(Self::as_value).collect())
+            }
+            OwnedValue::BulkBytes(bytes) => Value::BulkBytes(bytes),
+            OwnedValue::Streamed(_) => Value::Null,
+        }
+    }
+}
+
+impl From<&Value<'_>> for OwnedValue {
+    fn from(value: &Value<'_>) -> Self {
+        match value {
+            Value::SimpleString(s) => OwnedValue::SimpleString(s.to_string()),
+            Value::SimpleError(s) => OwnedValue::SimpleError(s.to_string()),
+            Value::Integer(i) => OwnedValue::Integer(*i),
+            Value::BulkString(s) => OwnedValue::BulkString(s.to_string()),
+            Value::Array(values) => OwnedValue::Array(values.iter().map(Self::from).collect()),
+            Value::Null => OwnedValue::Null,
+            Value::Boolean(b) => OwnedValue::Boolean(*b),
+            Value::Double(s) => OwnedValue::Double(s.clone()),
+            Value::BigNumber(s) => OwnedValue::BigNumber(s.to_string()),
+            Value::BulkError(s) => OwnedValue::BulkError(s.to_string()),
+            Value::VerbatimString(format, s) => OwnedValue::VerbatimString(*format, s.to_string()),
+            Value::Map(keys, values) => OwnedValue::Map(
+                keys.iter().map(Self::from).collect(),
+                values.iter().map(Self::from).collect(),
+            ),
+            Value::Set(values) => OwnedValue::Set(values.iter().map(Self::from).collect()),
+            Value::Pushes(values) => OwnedValue::Pushes(values.iter().map(Self::from).collect()),
+            Value::BulkBytes(bytes) => OwnedValue::BulkBytes(bytes.to_vec()),
+        }
+    }
+}
+
+impl From<Value<'_>> for OwnedValue {
+    fn from(value: Value<'_>) -> Self {
+        OwnedValue::from(&value)
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Helper method that converts the current value into an [OwnedValue]
+    pub fn to_owned_value(&self) -> OwnedValue {
+        OwnedValue::from(self)
+    }
+}
+
+impl ToResp for OwnedValue {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        self.as_value().write_resp(out, protocol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_roundtrip() {
+        let message = "*4\r\n$5\r\nhello\r\n%1\r\n+a\r\n,1.5\r\n~2\r\n:1\r\n_\r\n=7\r\nmkd:# t\r\n";
+        let value = parse(message).unwrap();
+        let owned = value.to_owned_value();
+        assert_eq!(
+            owned,
+            OwnedValue::Array(vec![
+                OwnedValue::BulkString("hello".to_string()),
+                OwnedValue::Map(
+                    vec![OwnedValue::SimpleString("a".to_string())],
+                    vec![OwnedValue::Double("1.5".to_string())]
+                ),
+                OwnedValue::Set(BTreeSet::from([OwnedValue::Integer(1), OwnedValue::Null])),
+                OwnedValue::VerbatimString(VerbatimFormat::Mkd, "# t".to_string()),
+            ])
+        );
+        assert_eq!(owned.as_value(), value);
+        assert_eq!(owned.to_resp(Protocol::Resp3), value.encode());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::Value;
+
+/// A message published on a channel (`message` and `smessage` push types)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PubSubMessage<'a> {
+    pub channel: &'a str,
+    pub payload: &'a str,
+}
+
+/// A message published on a channel matching a subscribed pattern (`pmessage` push type)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PatternMessage<'a> {
+    pub pattern: &'a str,
+    pub channel: &'a str,
+    pub payload: &'a str,
+}
+
+/// The confirmation of a subscription change (`subscribe`, `unsubscribe`, `psubscribe`,
+/// `punsubscribe`, `ssubscribe` and `sunsubscribe` push types)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Subscription<'a> {
+    /// The channel (or pattern) affected by the change.
+    /// This is `None` when unsubscribing from all the channels while not being subscribed to any.
+    pub channel: Option<&'a str>,
+    /// The number of channels the client is still subscribed to
+    pub count: i64,
+}
+
+/// A client side caching invalidation message (`invalidate` push type)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Invalidation<'a> {
+    /// The keys that have been invalidated.
+    /// This is `None` when the server flushed the whole keyspace and every cached key should be evicted.
+    pub keys: Option<Vec<&'a str>>,
+}
+
+/// Represents a decoded push message (see [Value::as_push])
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PushKind<'a> {
+    Message(PubSubMessage<'a>),
+    PMessage(PatternMessage<'a>),
+    SMessage(PubSubMessage<'a>),
+    Subscribe(Subscription<'a>),
+    Unsubscribe(Subscription<'a>),
+    PSubscribe(Subscription<'a>),
+    PUnsubscribe(Subscription<'a>),
+    SSubscribe(Subscription<'a>),
+    SUnsubscribe(Subscription<'a>),
+    Invalidate(Invalidation<'a>),
+    /// Any push message with an unknown kind or with an unexpected shape.
+    /// It contains all the elements of the push message, including the kind.
+    Other(&'a [Value<'a>]),
+}
+
+impl<'a> PushKind<'a> {
+    /// Decodes the elements of a push message.
+    ///
+    /// This is what [Value::as_push] uses internally, but it can also be used directly on the
+    /// elements of a [Value::Array] to decode Pub/Sub messages received over RESP2,
+    /// where push messages are delivered as regular arrays.
+    pub fn from_values(values: &'a [Value<'a>]) -> Self {
+        Self::decode(values).unwrap_or(PushKind::Other(values))
+    }
+
+    fn decode(values: &'a [Value<'a>]) -> Option<Self> {
+        let (kind, args) = values.split_first()?;
+        let push = match (kind.as_str()?, args) {
+            ("message", [channel, payload]) => PushKind::Message(PubSubMessage {
+                channel: channel.as_str()?,
+                payload: payload.as_str()?,
+            }),
+            ("smessage", [channel, payload]) => PushKind::SMessage(PubSubMessage {
+                channel: channel.as_str()?,
+                payload: payload.as_str()?,
+            }),
+            ("pmessage", [pattern, channel, payload]) => PushKind::PMessage(PatternMessage {
+                pattern: pattern.as_str()?,
+                channel: channel.as_str()?,
+                payload: payload.as_str()?,
+            }),
+            ("subscribe", [channel, count]) => {
+                PushKind::Subscribe(Subscription::decode(channel, count)?)
+            }
+            ("unsubscribe", [channel, count]) => {
+                PushKind::Unsubscribe(Subscription::decode(channel, count)?)
+            }
+            ("psubscribe", [channel, count]) => {
+                PushKind::PSubscribe(Subscription::decode(channel, count)?)
+            }
+            ("punsubscribe", [channel, count]) => {
+                PushKind::PUnsubscribe(Subscription::decode(channel, count)?)
+            }
+            ("ssubscribe", [channel, count]) => {
+                PushKind::SSubscribe(Subscription::decode(channel, count)?)
+            }
+            ("sunsubscribe", [channel, count]) => {
+                PushKind::SUnsubscribe(Subscription::decode(channel, count)?)
+            }
+            ("invalidate", [keys]) => PushKind::Invalidate(Invalidation::decode(keys)?),
+            _ => return None,
+        };
+        Some(push)
+    }
+}
+
+impl<'a> Subscription<'a> {
+    fn decode(channel: &'a Value<'a>, count: &'a Value<'a>) -> Option<Self> {
+        let channel = match channel {
+            Value::Null => None,
+            channel => Some(channel.as_str()?),
+        };
+        Some(Subscription {
+            channel,
+            count: count.as_i64()?,
+        })
+    }
+}
+
+impl<'a> Invalidation<'a> {
+    fn decode(keys: &'a Value<'a>) -> Option<Self> {
+        let keys = match keys {
+            Value::Null => None,
+            keys => Some(
+                keys.as_array()?
+                    .iter()
+                    .map(|key| key.as_str())
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+        };
+        Some(Invalidation { keys })
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Helper method that tries to decode the current value as a well-known push message.
+    /// This will return `Some(PushKind)` for [Value::Pushes]. Push messages with an unknown kind
+    /// (or with an unexpected shape) are returned as [PushKind::Other].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::{parse, PubSubMessage, PushKind};
+    ///
+    /// let value = parse(">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n").unwrap();
+    /// assert_eq!(
+    ///     value.as_push(),
+    ///     Some(PushKind::Message(PubSubMessage {
+    ///         channel: "news",
+    ///         payload: "hello"
+    ///     }))
+    /// );
+    /// ```
+    pub fn as_push(&self) -> Option<PushKind<'_>> {
+        match self {
+            Value::Pushes(values) => Some(PushKind::from_values(values)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        let value = Value::Pushes(vec![
+            Value::BulkString("message"),
+            Value::BulkString("news"),
+            Value::BulkString("hello"),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::Message(PubSubMessage {
+                channel: "news",
+                payload: "hello"
+            }))
+        );
+
+        let value = Value::Pushes(vec![
+            Value::BulkString("smessage"),
+            Value::BulkString("news"),
+            Value::BulkString("hello"),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::SMessage(PubSubMessage {
+                channel: "news",
+                payload: "hello"
+            }))
+        );
+    }
+
+    #[test]
+    fn test_pmessage() {
+        let value = Value::Pushes(vec![
+            Value::BulkString("pmessage"),
+            Value::BulkString("news.*"),
+            Value::BulkString("news.tech"),
+            Value::BulkString("hello"),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::PMessage(PatternMessage {
+                pattern: "news.*",
+                channel: "news.tech",
+                payload: "hello"
+            }))
+        );
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let value = Value::Pushes(vec![
+            Value::BulkString("subscribe"),
+            Value::BulkString("news"),
+            Value::Integer(1),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::Subscribe(Subscription {
+                channel: Some("news"),
+                count: 1
+            }))
+        );
+
+        let value = Value::Pushes(vec![
+            Value::BulkString("psubscribe"),
+            Value::BulkString("news.*"),
+            Value::Integer(2),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::PSubscribe(Subscription {
+                channel: Some("news.*"),
+                count: 2
+            }))
+        );
+
+        let value = Value::Pushes(vec![
+            Value::BulkString("unsubscribe"),
+            Value::Null,
+            Value::Integer(0),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::Unsubscribe(Subscription {
+                channel: None,
+                count: 0
+            }))
+        );
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let value = Value::Pushes(vec![
+            Value::BulkString("invalidate"),
+            Value::Array(vec![Value::BulkString("foo"), Value::BulkString("bar")]),
+        ]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::Invalidate(Invalidation {
+                keys: Some(vec!["foo", "bar"])
+            }))
+        );
+
+        let value = Value::Pushes(vec![Value::BulkString("invalidate"), Value::Null]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::Invalidate(Invalidation { keys: None }))
+        );
+    }
+
+    #[test]
+    fn test_other() {
+        // unknown kind
+        let value = Value::Pushes(vec![Value::BulkString("custom"), Value::Integer(1)]);
+        assert_eq!(
+            value.as_push(),
+            Some(PushKind::Other(&[
+                Value::BulkString("custom"),
+                Value::Integer(1)
+            ]))
+        );
+
+        // known kind with an unexpected shape
+        let value = Value::Pushes(vec![Value::BulkString("message"), Value::Integer(1)]);
+        assert!(matches!(value.as_push(), Some(PushKind::Other(_))));
+
+        // empty push
+        let value = Value::Pushes(vec![]);
+        assert_eq!(value.as_push(), Some(PushKind::Other(&[])));
+
+        // not a push
+        let value = Value::Array(vec![]);
+        assert_eq!(value.as_push(), None);
+    }
+
+    #[test]
+    fn test_from_values() {
+        // RESP2 Pub/Sub messages are delivered as arrays
+        let value = Value::Array(vec![
+            Value::BulkString("message"),
+            Value::BulkString("news"),
+            Value::BulkString("hello"),
+        ]);
+        assert_eq!(
+            PushKind::from_values(value.as_array().unwrap()),
+            PushKind::Message(PubSubMessage {
+                channel: "news",
+                payload: "hello"
+            })
+        );
+    }
+}
@@ -0,0 +1,469 @@
+//! Typed decoding of the replies of common Redis commands.
+//!
+//! Every function in this module accepts a parsed [Value] and takes care of handling both the
+//! RESP2 shape (e.g. flat arrays of alternating keys and values, scores as bulk strings) and the
+//! RESP3 shape (e.g. maps, doubles) of the reply.
+//!
+//! # Example
+//!
+//! ```
+//! use tinyresp::{parse, replies};
+//!
+//! // RESP2 reply to `HGETALL`
+//! let value = parse("*4\r\n$4\r\nname\r\n$4\r\nJohn\r\n$3\r\nage\r\n$2\r\n42\r\n").unwrap();
+//! let hash = replies::hgetall(&value).unwrap();
+//! assert_eq!(hash.get("name"), Some(&"John"));
+//!
+//! // RESP3 reply to `HGETALL`
+//! let value = parse("%2\r\n$4\r\nname\r\n$4\r\nJohn\r\n$3\r\nage\r\n$2\r\n42\r\n").unwrap();
+//! let hash = replies::hgetall(&value).unwrap();
+//! assert_eq!(hash.get("age"), Some(&"42"));
+//! ```
+
+use crate::Value;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Represents an error that can occur when decoding a reply
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ReplyError {
+    #[error("The server replied with an error: {0}")]
+    ServerError(String),
+    #[error("Unexpected reply, expected {0}")]
+    UnexpectedReply(&'static str),
+}
+
+/// An entry of a stream, as returned by `XRANGE`, `XREVRANGE` and `XREAD`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StreamEntry<'a> {
+    pub id: &'a str,
+    /// The fields of the entry, in the order they were added
+    pub fields: Vec<(&'a str, &'a str)>,
+}
+
+/// The entries read from a single stream by `XREAD` or `XREADGROUP`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StreamRead<'a> {
+    pub stream: &'a str,
+    pub entries: Vec<StreamEntry<'a>>,
+}
+
+/// A member of a sorted set with its score, as returned by `ZRANGE ... WITHSCORES`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScoredMember<'a> {
+    pub member: &'a str,
+    pub score: f64,
+}
+
+/// A page of results returned by `SCAN`, `SSCAN` and the other cursor based commands
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScanPage<'a> {
+    /// The cursor to use for the next call. The iteration is complete when this is `0`.
+    pub cursor: u64,
+    pub keys: Vec<&'a str>,
+}
+
+/// A single client connection, as described by a line of the `CLIENT LIST` output
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ClientInfo<'a> {
+    /// All the `key=value` properties of the client, in the order they appear
+    pub fields: Vec<(&'a str, &'a str)>,
+}
+
+/// A section of the `INFO` output (e.g. `# Server`)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InfoSection<'a> {
+    /// The name of the section, this is empty for fields that appear before any section header
+    pub name: &'a str,
+    pub fields: Vec<(&'a str, &'a str)>,
+}
+
+/// The output of the `INFO` command
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Info<'a> {
+    pub sections: Vec<InfoSection<'a>>,
+}
+
+impl<'a> ClientInfo<'a> {
+    /// Returns the value of the given property (e.g. `addr` or `name`)
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        find(&self.fields, key)
+    }
+
+    /// Returns the unique id of the client
+    pub fn id(&self) -> Option<u64> {
+        self.get("id")?.parse().ok()
+    }
+
+    /// Returns the address of the client
+    pub fn addr(&self) -> Option<&'a str> {
+        self.get("addr")
+    }
+
+    /// Returns the name of the client (if it was set with `CLIENT SETNAME`)
+    pub fn name(&self) -> Option<&'a str> {
+        self.get("name").filter(|name| !name.is_empty())
+    }
+}
+
+impl<'a> Info<'a> {
+    /// Returns the value of the given field, looking into every section
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.sections
+            .iter()
+            .find_map(|section| find(&section.fields, key))
+    }
+
+    /// Returns the section with the given name (case insensitive)
+    pub fn section(&self, name: &str) -> Option<&InfoSection<'a>> {
+        self.sections
+            .iter()
+            .find(|section| section.name.eq_ignore_ascii_case(name))
+    }
+}
+
+fn find<'a>(fields: &[(&'a str, &'a str)], key: &str) -> Option<&'a str> {
+    fields.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// Makes sure the given reply is not an error
+fn check<'a, 'b>(value: &'b Value<'a>) -> Result<&'b Value<'a>, ReplyError> {
+    match value {
+        Value::SimpleError(e) | Value::BulkError(e) => Err(ReplyError::ServerError(e.to_string())),
+        value => Ok(value),
+    }
+}
+
+fn string<'a>(value: &'a Value<'a>) -> Result<&'a str, ReplyError> {
+    value
+        .as_str()
+        .ok_or(ReplyError::UnexpectedReply("a string"))
+}
+
+fn array<'a>(value: &'a Value<'a>) -> Result<&'a [Value<'a>], ReplyError> {
+    match value {
+        Value::Array(values) | Value::Pushes(values) => Ok(values),
+        _ => Err(ReplyError::UnexpectedReply("an array")),
+    }
+}
+
+fn score(value: &Value<'_>) -> Result<f64, ReplyError> {
+    match value {
+        Value::Double(_) => value.as_f64(),
+        Value::Integer(i) => Some(*i as f64),
+        value => value.as_str().and_then(|s| s.parse().ok()),
+    }
+    .ok_or(ReplyError::UnexpectedReply("a score"))
+}
+
+/// Returns the key/value pairs of a RESP3 map or of a RESP2 flat array of alternating keys and values
+fn pairs<'a>(value: &'a Value<'a>) -> Result<Vec<(&'a Value<'a>, &'a Value<'a>)>, ReplyError> {
+    match check(value)? {
+        Value::Map(keys, values) => Ok(keys.iter().zip(values.iter()).collect()),
+        Value::Array(values) if values.len() % 2 == 0 => Ok(values
+            .chunks_exact(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .collect()),
+        _ => Err(ReplyError::UnexpectedReply("a map or an array of pairs")),
+    }
+}
+
+fn string_pairs<'a>(value: &'a Value<'a>) -> Result<Vec<(&'a str, &'a str)>, ReplyError> {
+    pairs(value)?
+        .into_iter()
+        .map(|(k, v)| Ok((string(k)?, string(v)?)))
+        .collect()
+}
+
+fn stream_entry<'a>(value: &'a Value<'a>) -> Result<StreamEntry<'a>, ReplyError> {
+    match array(value)? {
+        [id, Value::Null] => Ok(StreamEntry {
+            id: string(id)?,
+            fields: Vec::new(),
+        }),
+        [id, fields] => Ok(StreamEntry {
+            id: string(id)?,
+            fields: string_pairs(fields)?,
+        }),
+        _ => Err(ReplyError::UnexpectedReply("a stream entry")),
+    }
+}
+
+/// Decodes the reply of `XRANGE` and `XREVRANGE`
+pub fn xrange<'a>(value: &'a Value<'a>) -> Result<Vec<StreamEntry<'a>>, ReplyError> {
+    array(check(value)?)?.iter().map(stream_entry).collect()
+}
+
+/// Decodes the reply of `XREAD` and `XREADGROUP`.
+/// RESP2 servers reply with an array of `[stream, entries]` pairs while RESP3 servers reply with a map.
+/// A null reply (no new entries before the timeout expired) is decoded as an empty list.
+pub fn xread<'a>(value: &'a Value<'a>) -> Result<Vec<StreamRead<'a>>, ReplyError> {
+    let streams: Vec<(&Value, &Value)> = match check(value)? {
+        Value::Null => return Ok(Vec::new()),
+        Value::Map(keys, values) => keys.iter().zip(values.iter()).collect(),
+        value => array(value)?
+            .iter()
+            .map(|stream| match array(stream)? {
+                [name, entries] => Ok((name, entries)),
+                _ => Err(ReplyError::UnexpectedReply("a [stream, entries] pair")),
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    streams
+        .into_iter()
+        .map(|(name, entries)| {
+            Ok(StreamRead {
+                stream: string(name)?,
+                entries: xrange(entries)?,
+            })
+        })
+        .collect()
+}
+
+/// Decodes the reply of `ZRANGE`, `ZRANGEBYSCORE` and similar commands when invoked with `WITHSCORES`.
+/// RESP2 servers reply with a flat array of alternating members and scores (as bulk strings)
+/// while RESP3 servers reply with an array of `[member, score]` pairs (with scores as doubles).
+pub fn zrange_withscores<'a>(value: &'a Value<'a>) -> Result<Vec<ScoredMember<'a>>, ReplyError> {
+    let values = array(check(value)?)?;
+    if values.iter().all(Value::is_array) {
+        values
+            .iter()
+            .map(|pair| match array(pair)? {
+                [member, score_value] => Ok(ScoredMember {
+                    member: string(member)?,
+                    score: score(score_value)?,
+                }),
+                _ => Err(ReplyError::UnexpectedReply("a [member, score] pair")),
+            })
+            .collect()
+    } else {
+        pairs(value)?
+            .into_iter()
+            .map(|(member, score_value)| {
+                Ok(ScoredMember {
+                    member: string(member)?,
+                    score: score(score_value)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Decodes the reply of `SCAN`, `SSCAN`, `HSCAN` and `ZSCAN`.
+/// For `HSCAN` and `ZSCAN` the keys contain alternating fields (or members) and values (or scores).
+pub fn scan<'a>(value: &'a Value<'a>) -> Result<ScanPage<'a>, ReplyError> {
+    match array(check(value)?)? {
+        [cursor, keys] => Ok(ScanPage {
+            cursor: string(cursor)?
+                .parse()
+                .map_err(|_| ReplyError::UnexpectedReply("a numeric cursor"))?,
+            keys: array(keys)?.iter().map(string).collect::<Result<_, _>>()?,
+        }),
+        _ => Err(ReplyError::UnexpectedReply("a [cursor, keys] pair")),
+    }
+}
+
+/// Decodes the reply of `HGETALL`.
+/// RESP2 servers reply with a flat array of alternating fields and values while RESP3 servers reply with a map.
+pub fn hgetall<'a>(value: &'a Value<'a>) -> Result<HashMap<&'a str, &'a str>, ReplyError> {
+    Ok(string_pairs(value)?.into_iter().collect())
+}
+
+/// Decodes the reply of `CLIENT LIST` (one [ClientInfo] per connected client).
+/// This also works for the reply of `CLIENT INFO`.
+pub fn client_list<'a>(value: &'a Value<'a>) -> Result<Vec<ClientInfo<'a>>, ReplyError> {
+    Ok(string(check(value)?)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| ClientInfo {
+            fields: line
+                .split_whitespace()
+                .filter_map(|field| field.split_once('='))
+                .collect(),
+        })
+        .collect())
+}
+
+/// Decodes the reply of `INFO`.
+/// RESP2 servers reply with a bulk string while RESP3 servers reply with a verbatim string.
+pub fn info<'a>(value: &'a Value<'a>) -> Result<Info<'a>, ReplyError> {
+    let mut sections = Vec::new();
+    let mut current = InfoSection {
+        name: "",
+        fields: Vec::new(),
+    };
+
+    for line in string(check(value)?)?.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('#') {
+            let next = InfoSection {
+                name: name.trim(),
+                fields: Vec::new(),
+            };
+            let previous = std::mem::replace(&mut current, next);
+            if !previous.name.is_empty() || !previous.fields.is_empty() {
+                sections.push(previous);
+            }
+        } else if let Some(field) = line.split_once(':') {
+            current.fields.push(field);
+        }
+    }
+    if !current.name.is_empty() || !current.fields.is_empty() {
+        sections.push(current);
+    }
+
+    Ok(Info { sections })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, VerbatimFormat};
+
+    #[test]
+    fn test_errors() {
+        let value = Value::SimpleError("ERR wrong number of arguments");
+        assert_eq!(
+            hgetall(&value),
+            Err(ReplyError::ServerError(
+                "ERR wrong number of arguments".to_string()
+            ))
+        );
+        assert_eq!(
+            xrange(&Value::Integer(1)),
+            Err(ReplyError::UnexpectedReply("an array"))
+        );
+    }
+
+    #[test]
+    fn test_xrange() {
+        let value = parse("*2\r\n*2\r\n$3\r\n1-0\r\n*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$1\r\na\r\n$1\r\n3\r\n").unwrap();
+        assert_eq!(
+            xrange(&value).unwrap(),
+            vec![
+                StreamEntry {
+                    id: "1-0",
+                    fields: vec![("a", "1"), ("b", "2")]
+                },
+                StreamEntry {
+                    id: "2-0",
+                    fields: vec![("a", "3")]
+                }
+            ]
+        );
+        assert_eq!(xrange(&Value::Array(vec![])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_xread() {
+        let entries = "*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n";
+        let expected = vec![StreamRead {
+            stream: "s",
+            entries: vec![StreamEntry {
+                id: "1-0",
+                fields: vec![("a", "1")],
+            }],
+        }];
+
+        // RESP2
+        let resp2 = format!("*1\r\n*2\r\n$1\r\ns\r\n{entries}");
+        assert_eq!(xread(&parse(&resp2).unwrap()).unwrap(), expected);
+
+        // RESP3
+        let resp3 = format!("%1\r\n$1\r\ns\r\n{entries}");
+        assert_eq!(xread(&parse(&resp3).unwrap()).unwrap(), expected);
+
+        // timeout
+        assert_eq!(xread(&Value::Null).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_zrange_withscores() {
+        let expected = vec![
+            ScoredMember {
+                member: "a",
+                score: 1.0,
+            },
+            ScoredMember {
+                member: "b",
+                score: f64::INFINITY,
+            },
+        ];
+
+        // RESP2
+        let value = parse("*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$3\r\ninf\r\n").unwrap();
+        assert_eq!(zrange_withscores(&value).unwrap(), expected);
+
+        // RESP3
+        let value = parse("*2\r\n*2\r\n$1\r\na\r\n,1\r\n*2\r\n$1\r\nb\r\n,inf\r\n").unwrap();
+        assert_eq!(zrange_withscores(&value).unwrap(), expected);
+
+        // odd number of elements
+        let value = parse("*1\r\n$1\r\na\r\n").unwrap();
+        assert!(zrange_withscores(&value).is_err());
+    }
+
+    #[test]
+    fn test_scan() {
+        let value = parse("*2\r\n$2\r\n17\r\n*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n").unwrap();
+        assert_eq!(
+            scan(&value).unwrap(),
+            ScanPage {
+                cursor: 17,
+                keys: vec!["foo", "bar"]
+            }
+        );
+
+        let value = parse("*2\r\n$3\r\nabc\r\n*0\r\n").unwrap();
+        assert_eq!(
+            scan(&value),
+            Err(ReplyError::UnexpectedReply("a numeric cursor"))
+        );
+    }
+
+    #[test]
+    fn test_hgetall() {
+        let value = parse("*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n").unwrap();
+        let hash = hgetall(&value).unwrap();
+        assert_eq!(hash.len(), 2);
+        assert_eq!(hash.get("a"), Some(&"1"));
+        assert_eq!(hash.get("b"), Some(&"2"));
+
+        let value = parse("%1\r\n$1\r\na\r\n$1\r\n1\r\n").unwrap();
+        let hash = hgetall(&value).unwrap();
+        assert_eq!(hash.get("a"), Some(&"1"));
+    }
+
+    #[test]
+    fn test_client_list() {
+        let value = Value::BulkString(
+            "id=3 addr=127.0.0.1:50188 name= db=0 cmd=client|list\nid=4 addr=127.0.0.1:50190 name=worker db=1 cmd=get\n",
+        );
+        let clients = client_list(&value).unwrap();
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].id(), Some(3));
+        assert_eq!(clients[0].addr(), Some("127.0.0.1:50188"));
+        assert_eq!(clients[0].name(), None);
+        assert_eq!(clients[1].name(), Some("worker"));
+        assert_eq!(clients[1].get("db"), Some("1"));
+        assert_eq!(clients[1].get("unknown"), None);
+    }
+
+    #[test]
+    fn test_info() {
+        let text = "# Server\r\nredis_version:7.2.4\r\nredis_mode:standalone\r\n\r\n# Clients\r\nconnected_clients:1\r\n";
+        for value in [
+            Value::BulkString(text),
+            Value::VerbatimString(VerbatimFormat::Txt, text),
+        ] {
+            let info = info(&value).unwrap();
+            assert_eq!(info.sections.len(), 2);
+            assert_eq!(info.get("redis_version"), Some("7.2.4"));
+            assert_eq!(info.get("connected_clients"), Some("1"));
+            assert_eq!(info.get("unknown"), None);
+            let section = info.section("clients").unwrap();
+            assert_eq!(section.name, "Clients");
+            assert_eq!(section.fields, vec![("connected_clients", "1")]);
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! A minimal framework to build services that speak RESP (and can therefore be driven with `redis-cli`).
+//!
+//! Every connection is served by a dedicated thread. Commands can be sent either as multibulk
+//! arrays (the format used by every Redis client) or inline (e.g. `PING\r\n`, the format used
+//! when typing into `telnet`) and they are dispatched to the handler registered for their name.
+//!
+//! The following commands are handled out of the box: `PING`, `ECHO`, `HELLO`, `QUIT` and `COMMAND`.
+//!
+//! Arguments are binary safe when sent as multibulk. Inline commands are limited to
+//! [MAX_INLINE_SIZE] bytes, like in Redis.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::net::TcpListener;
+//! use tinyresp::{server::Server, OwnedValue};
+//!
+//! let server = Server::new().handler("TIME", |_command, _session| {
+//!     OwnedValue::Integer(42)
+//! });
+//! server.serve(TcpListener::bind("127.0.0.1:6379").unwrap()).unwrap();
+//! ```
+
+use crate::{frame_len, Command, Decoder, OwnedValue, Protocol, ToResp};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// The maximum length of an inline command (longer lines are rejected with a protocol error)
+pub const MAX_INLINE_SIZE: usize = 64 * 1024;
+
+/// A function that handles a command and returns the reply to send to the client
+pub type Handler = Box<dyn Fn(&Command, &Session) -> OwnedValue + Send + Sync>;
+
+/// The state of a client connection
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Session {
+    id: u64,
+    protocol: Protocol,
+    name: Option<String>,
+}
+
+impl Session {
+    /// Returns the unique id of the connection
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the protocol negotiated with `HELLO` (connections start with RESP2)
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// Returns the name of the connection (if it was set with `HELLO ... SETNAME`)
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// A RESP server dispatching commands to the registered handlers
+pub struct Server {
+    name: String,
+    version: String,
+    handlers: BTreeMap<String, Handler>,
+    next_id: AtomicU64,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    /// Creates a new server with no handlers (only the built-in commands)
+    pub fn new() -> Self {
+        Server {
+            name: "tinyresp".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            handlers: BTreeMap::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Sets the server name and version reported by `HELLO`
+    pub fn info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.version = version.into();
+        self
+    }
+
+    /// Registers the handler for the given command (command names are case insensitive).
+    /// Handlers take precedence over the built-in commands.
+    pub fn handler<F>(mut self, name: &str, handler: F) -> Self
+    where
+        F: Fn(&Command, &Session) -> OwnedValue + Send + Sync + 'static,
+    {
+        self.handlers
+            .insert(name.to_ascii_uppercase(), Box::new(handler));
+        self
+    }
+
+    /// Accepts connections from the given listener, serving each of them in a dedicated thread.
+    /// This function only returns if accepting a connection fails.
+    pub fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = Arc::clone(&server);
+            thread::spawn(move || server.handle_connection(stream));
+        }
+        Ok(())
+    }
+
+    /// Serves a single connection until the client disconnects or sends `QUIT`
+    pub fn handle_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut session = Session {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            protocol: Protocol::Resp2,
+            name: None,
+        };
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            let command = match next_command(&mut buffer) {
+                Ok(Some(Some(command))) => command,
+                // empty inline command
+                Ok(Some(None)) => continue,
+                Ok(None) => {
+                    let read = stream.read(&mut chunk)?;
+                    if read == 0 {
+                        return Ok(());
+                    }
+                    buffer.extend_from_slice(&chunk[..read]);
+                    continue;
+                }
+                Err(message) => {
+                    let reply = OwnedValue::SimpleError(format!("ERR Protocol error: {}", message));
+                    stream.write_all(&reply.to_resp(session.protocol))?;
+                    return Ok(());
+                }
+            };
+
+            let quit = command
+                .name()
+                .is_some_and(|name| name.eq_ignore_ascii_case("QUIT"));
+            let reply = self.dispatch(&command, &mut session);
+            stream.write_all(&reply.to_resp(session.protocol))?;
+            if quit {
+                return Ok(());
+            }
+        }
+    }
+
+    fn dispatch(&self, command: &Command, session: &mut Session) -> OwnedValue {
+        let Some(name) = command.name().map(str::to_ascii_uppercase) else {
+            return OwnedValue::SimpleError(format!(
+                "ERR unknown command '{}'",
+                command.parts()[0].escape_ascii()
+            ));
+        };
+        if let Some(handler) = self.handlers.get(&name) {
+            return handler(command, session);
+        }
+
+        let args: Vec<&[u8]> = command.parts()[1..].iter().map(Vec::as_slice).collect();
+        match (name.as_str(), args.as_slice()) {
+            ("PING", []) => OwnedValue::SimpleString("PONG".to_string()),
+            ("PING", [message]) | ("ECHO", [message]) => bulk(message),
+            ("QUIT", _) => OwnedValue::SimpleString("OK".to_string()),
+            ("HELLO", args) => self.hello(args, session),
+            ("COMMAND", args) => self.command(args),
+            ("PING" | "ECHO", _) => wrong_arity(&name),
+            _ => OwnedValue::SimpleError(format!(
+                "ERR unknown command '{}', with args beginning with: {}",
+                command.name().unwrap_or_default(),
+                args.iter()
+                    .map(|arg| format!("'{}'", arg.escape_ascii()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )),
+        }
+    }
+
+    fn hello(&self, args: &[&[u8]], session: &mut Session) -> OwnedValue {
+        let mut protocol = session.protocol;
+        let mut name = session.name.clone();
+        if let Some((version, mut options)) = args.split_first() {
+            protocol = match *version {
+                b"2" => Protocol::Resp2,
+                b"3" => Protocol::Resp3,
+                _ => {
+                    return OwnedValue::SimpleError(
+                        "NOPROTO unsupported protocol version".to_string(),
+                    )
+                }
+            };
+            loop {
+                options = match options {
+                    // credentials are accepted but not verified: authentication is up to the application
+                    [option, _username, _password, rest @ ..]
+                        if option.eq_ignore_ascii_case(b"AUTH") =>
+                    {
+                        rest
+                    }
+                    [option, value, rest @ ..] if option.eq_ignore_ascii_case(b"SETNAME") => {
+                        match std::str::from_utf8(value) {
+                            Ok(value) => name = Some(value.to_string()),
+                            Err(_) => {
+                                return OwnedValue::SimpleError(
+                                    "ERR Client names must be valid UTF-8".to_string(),
+                                )
+                            }
+                        }
+                        rest
+                    }
+                    [] => break,
+                    _ => return OwnedValue::SimpleError("ERR syntax error".to_string()),
+                }
+            }
+        }
+        session.protocol = protocol;
+        session.name = name;
+
+        let fields = [
+            ("server", OwnedValue::BulkString(self.name.clone())),
+            ("version", OwnedValue::BulkString(self.version.clone())),
+            (
+                "proto",
+                OwnedValue::Integer(match protocol {
+                    Protocol::Resp2 => 2,
+                    Protocol::Resp3 => 3,
+                }),
+            ),
+            ("id", OwnedValue::Integer(session.id as i64)),
+            ("mode", OwnedValue::BulkString("standalone".to_string())),
+            ("role", OwnedValue::BulkString("master".to_string())),
+            ("modules", OwnedValue::Array(Vec::new())),
+        ];
+        let (keys, values) = fields
+            .into_iter()
+            .map(|(key, value)| (OwnedValue::BulkString(key.to_string()), value))
+            .unzip();
+        OwnedValue::Map(keys, values)
+    }
+
+    fn command(&self, args: &[&[u8]]) -> OwnedValue {
+        let mut names: Vec<&str> = ["PING", "ECHO", "HELLO", "QUIT", "COMMAND"]
+            .into_iter()
+            .chain(self.handlers.keys().map(String::as_str))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        match args
+            .first()
+            .map(|arg| String::from_utf8_lossy(arg).to_ascii_uppercase())
+            .as_deref()
+        {
+            // every command is described as variadic and without keys
+            None => OwnedValue::Array(
+                names
+                    .into_iter()
+                    .map(|name| {
+                        OwnedValue::Array(vec![
+                            OwnedValue::BulkString(name.to_ascii_lowercase()),
+                            OwnedValue::Integer(-1),
+                            OwnedValue::Array(Vec::new()),
+                            OwnedValue::Integer(0),
+                            OwnedValue::Integer(0),
+                            OwnedValue::Integer(0),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Some("COUNT") => OwnedValue::Integer(names.len() as i64),
+            Some("DOCS") => OwnedValue::Map(Vec::new(), Vec::new()),
+            Some(subcommand) => OwnedValue::SimpleError(format!(
+                "ERR unknown subcommand '{}'",
+                subcommand.to_ascii_lowercase()
+            )),
+        }
+    }
+}
+
+/// Replies with the given bytes, as a bulk string if they're valid UTF-8
+fn bulk(bytes: &[u8]) -> OwnedValue {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => OwnedValue::BulkString(s.to_string()),
+        Err(_) => OwnedValue::BulkBytes(bytes.to_vec()),
+    }
+}
+
+fn wrong_arity(name: &str) -> OwnedValue {
+    OwnedValue::SimpleError(format!(
+        "ERR wrong number of arguments for '{}' command",
+        name.to_ascii_lowercase()
+    ))
+}
+
+/// Decodes the next command from the buffer, removing it from the buffer.
+/// Returns `None` if the buffer doesn't contain a complete command yet
+/// and `Some(None)` for empty inline commands.
+fn next_command(buffer: &mut Vec<u8>) -> Result<Option<Option<Command>>, String> {
+    if buffer.is_empty() {
+        return Ok(None);
+    }
+
+    if buffer[0] != b'*' {
+        // inline command
+        let Some(end) = buffer.iter().position(|b| *b == b'\n') else {
+            if buffer.len() > MAX_INLINE_SIZE {
+                return Err("too big inline request".to_string());
+            }
+            return Ok(None);
+        };
+        if end > MAX_INLINE_SIZE {
+            return Err("too big inline request".to_string());
+        }
+        let mut parts = buffer[..end]
+            .split(u8::is_ascii_whitespace)
+            .filter(|part| !part.is_empty());
+        let command = parts.next().map(|name| Command::new(name).args(parts));
+        buffer.drain(..=end);
+        return Ok(Some(command));
+    }
+
+    let Some(len) = frame_len(buffer).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let mut values = Decoder::new()
+        .feed(&buffer[..len])
+        .map_err(|e| e.to_string())?;
+    buffer.drain(..len);
+    match values.pop() {
+        Some(OwnedValue::Array(parts)) if !parts.is_empty() => {
+            let parts = parts
+                .into_iter()
+                .map(|part| match part {
+                    OwnedValue::BulkString(s) => Ok(s.into_bytes()),
+                    OwnedValue::BulkBytes(bytes) => Ok(bytes),
+                    _ => Err("expected a bulk string".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some(Some(Command::new(&parts[0]).args(&parts[1..]))))
+        }
+        _ => Err("expected a non empty array of bulk strings".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Connection;
+    use crate::{FromResp, Hello, ServerHello};
+
+    fn start(server: Server) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || server.serve(listener));
+        addr
+    }
+
+    fn bulk(s: &str) -> OwnedValue {
+        OwnedValue::BulkString(s.to_string())
+    }
+
+    #[test]
+    fn test_builtin_commands() {
+        let addr = start(Server::new());
+        let mut connection = Connection::connect(addr).unwrap();
+
+        assert_eq!(
+            connection.query(&Command::new("PING")).unwrap(),
+            OwnedValue::SimpleString("PONG".to_string())
+        );
+        assert_eq!(
+            connection.query(&Command::new("ping").arg("hi")).unwrap(),
+            bulk("hi")
+        );
+        assert_eq!(
+            connection
+                .query(&Command::new("ECHO").arg("hello"))
+                .unwrap(),
+            bulk("hello")
+        );
+        assert_eq!(
+            connection.query(&Command::new("ECHO")).unwrap(),
+            OwnedValue::SimpleError("ERR wrong number of arguments for 'echo' command".to_string())
+        );
+        assert_eq!(
+            connection
+                .query(&Command::new("COMMAND").arg("COUNT"))
+                .unwrap(),
+            OwnedValue::Integer(5)
+        );
+        assert_eq!(
+            connection.query(&Command::new("NOPE").arg("x")).unwrap(),
+            OwnedValue::SimpleError(
+                "ERR unknown command 'NOPE', with args beginning with: 'x'".to_string()
+            )
+        );
+        assert_eq!(
+            connection.query(&Command::new("QUIT")).unwrap(),
+            OwnedValue::SimpleString("OK".to_string())
+        );
+        assert!(connection.recv().is_err());
+    }
+
+    #[test]
+    fn test_hello() {
+        let addr = start(Server::new().info("test-server", "1.2.3"));
+        let mut connection = Connection::connect(addr).unwrap();
+
+        // RESP2 replies are flat arrays
+        let reply = connection
+            .query(&Hello::new(Protocol::Resp2).command())
+            .unwrap();
+        assert!(reply.as_value().is_array());
+
+        let reply = connection
+            .query(&Hello::new(Protocol::Resp3).setname("app").command())
+            .unwrap();
+        let hello = ServerHello::from_resp(&reply.as_value()).unwrap();
+        assert_eq!(hello.server, "test-server");
+        assert_eq!(hello.version, "1.2.3");
+        assert_eq!(hello.proto, Protocol::Resp3);
+
+        assert_eq!(
+            connection.query(&Command::new("HELLO").arg("4")).unwrap(),
+            OwnedValue::SimpleError("NOPROTO unsupported protocol version".to_string())
+        );
+    }
+
+    #[test]
+    fn test_handlers() {
+        let server = Server::new()
+            .handler("whoami", |_, session| match session.name() {
+                Some(name) => OwnedValue::BulkString(name.to_string()),
+                None => OwnedValue::Null,
+            })
+            .handler("SUM", |command, _| {
+                let sum: f64 = command.parts()[1..]
+                    .iter()
+                    .filter_map(|arg| std::str::from_utf8(arg).ok()?.parse::<f64>().ok())
+                    .sum();
+                OwnedValue::Double(sum.to_string())
+            });
+        let addr = start(server);
+        let mut connection = Connection::connect(addr).unwrap();
+
+        assert_eq!(
+            connection.query(&Command::new("WHOAMI")).unwrap(),
+            OwnedValue::Null
+        );
+        // doubles are sent as bulk strings to RESP2 clients
+        assert_eq!(
+            connection
+                .query(&Command::new("SUM").args(["1", "2.5"]))
+                .unwrap(),
+            bulk("3.5")
+        );
+
+        connection
+            .query(&Hello::new(Protocol::Resp3).setname("app").command())
+            .unwrap();
+        assert_eq!(
+            connection.query(&Command::new("WHOAMI")).unwrap(),
+            bulk("app")
+        );
+        assert_eq!(
+            connection
+                .query(&Command::new("SUM").args(["1", "2.5"]))
+                .unwrap(),
+            OwnedValue::Double("3.5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_inline_commands() {
+        let addr = start(Server::new());
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"PING\r\n\r\nECHO hello\n").unwrap();
+        let mut connection = Connection::new(stream);
+        assert_eq!(
+            connection.recv().unwrap(),
+            OwnedValue::SimpleString("PONG".to_string())
+        );
+        assert_eq!(connection.recv().unwrap(), bulk("hello"));
+    }
+
+    #[test]
+    fn test_binary_arguments() {
+        let addr = start(Server::new());
+        let mut connection = Connection::connect(addr).unwrap();
+        assert_eq!(
+            connection
+                .query(&Command::new("ECHO").arg(b"\x00\xff"))
+                .unwrap(),
+            OwnedValue::BulkBytes(b"\x00\xff".to_vec())
+        );
+        assert_eq!(
+            connection
+                .query(&Command::new("HELLO").args([&b"3"[..], b"SETNAME", b"\xff"]))
+                .unwrap(),
+            OwnedValue::SimpleError("ERR Client names must be valid UTF-8".to_string())
+        );
+        assert_eq!(
+            connection.query(&Command::new(b"\xff")).unwrap(),
+            OwnedValue::SimpleError("ERR unknown command '\\xff'".to_string())
+        );
+        assert_eq!(
+            connection.query(&Command::new("PING")).unwrap(),
+            OwnedValue::SimpleString("PONG".to_string())
+        );
+    }
+
+    #[test]
+    fn test_inline_size_limit() {
+        let addr = start(Server::new());
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut line = b"ECHO ".to_vec();
+        line.resize(MAX_INLINE_SIZE + 1, b'x');
+        stream.write_all(&line).unwrap();
+        let mut connection = Connection::new(stream);
+        assert_eq!(
+            connection.recv().unwrap(),
+            OwnedValue::SimpleError("ERR Protocol error: too big inline request".to_string())
+        );
+        assert!(connection.recv().is_err());
+    }
+
+    #[test]
+    fn test_protocol_error() {
+        let addr = start(Server::new());
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"*1\r\n:1\r\n").unwrap();
+        let mut connection = Connection::new(stream);
+        assert!(matches!(
+            connection.recv().unwrap(),
+            OwnedValue::SimpleError(e) if e.starts_with("ERR Protocol error")
+        ));
+        assert!(connection.recv().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{length_header, Context, DepthGuard, Length, ParseOptions, Value};
+use nom::IResult;
+use std::ops::Range;
+
+/// A parsed value annotated with the position of its bytes in the input
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpannedValue<'a> {
+    /// The value of scalar types, or an empty aggregate of the right type
+    /// (the nested values are only stored in the children)
+    node: Value<'a>,
+    /// The position of the whole node, from the type byte to the last CRLF
+    pub span: Range<usize>,
+    /// The position of the header line (the type byte, the content or length, and the CRLF)
+    pub header: Range<usize>,
+    /// The nested nodes of aggregate types, in the order they appear in the input
+    /// (keys and values alternate for maps)
+    pub children: Vec<SpannedValue<'a>>,
+}
+
+impl<'a> SpannedValue<'a> {
+    /// Returns the bytes of the node in the given input (which must be the input that was parsed)
+    pub fn raw<'i>(&self, input: &'i str) -> &'i str {
+        &input[self.span.clone()]
+    }
+
+    /// Returns the parsed value (for aggregate types it also contains all the nested values)
+    pub fn value(&self) -> Value<'a> {
+        assemble(
+            self.node.clone(),
+            self.children.iter().map(SpannedValue::value),
+        )
+    }
+
+    /// Converts the node to the parsed value, without copying the nested values
+    pub fn into_value(self) -> Value<'a> {
+        assemble(
+            self.node,
+            self.children.into_iter().map(SpannedValue::into_value),
+        )
+    }
+}
+
+/// Fills an empty aggregate with the given values (scalars are returned as they are)
+fn assemble<'a>(node: Value<'a>, values: impl Iterator<Item = Value<'a>>) -> Value<'a> {
+    match node {
+        Value::Array(_) => Value::Array(values.collect()),
+        Value::Set(_) => Value::Set(values.collect()),
+        Value::Pushes(_) => Value::Pushes(values.collect()),
+        Value::Map(_, _) => {
+            let (mut keys, mut map_values) = (Vec::new(), Vec::new());
+            for (idx, value) in values.enumerate() {
+                if idx % 2 == 0 {
+                    keys.push(value);
+                } else {
+                    map_values.push(value);
+                }
+            }
+            Value::Map(keys, map_values)
+        }
+        scalar => scalar,
+    }
+}
+
+/// Parses a RESP value like [parse_value](crate::parse_value) does, annotating every node with its
+/// position in the input.
+///
+/// The spans are relative to the start of `input`. In case of error, the position of the malformed
+/// region can be computed from the remaining input contained in the error.
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{parse_value_spanned, Value};
+///
+/// let message = "*2\r\n$5\r\nhello\r\n:1\r\n";
+/// let (_, spanned) = parse_value_spanned(message).unwrap();
+/// assert_eq!(spanned.span, 0..19);
+/// assert_eq!(spanned.header, 0..4);
+/// assert_eq!(spanned.children[0].value(), Value::BulkString("hello"));
+/// assert_eq!(spanned.children[0].raw(message), "$5\r\nhello\r\n");
+/// assert_eq!(spanned.children[1].span, 15..19);
+/// ```
+pub fn parse_value_spanned(input: &str) -> IResult<&str, SpannedValue<'_>> {
+    parse_value_spanned_with(input, &ParseOptions::default())
+}
+
+/// Parses a RESP value with the given options like [parse_value_with](crate::parse_value_with) does,
+/// annotating every node with its position in the input (see [parse_value_spanned]).
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{parse_value_spanned_with, Leniency, ParseOptions};
+///
+/// let options = ParseOptions { leniency: Leniency::all(), ..ParseOptions::default() };
+/// let (_, spanned) = parse_value_spanned_with("*1\n:1\n", &options).unwrap();
+/// assert_eq!(spanned.header, 0..3);
+/// assert_eq!(spanned.children[0].span, 3..6);
+/// ```
+pub fn parse_value_spanned_with<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, SpannedValue<'a>> {
+    spanned(input, input, &Context::new(input, options))
+}
+
+fn spanned<'a>(
+    original: &'a str,
+    input: &'a str,
+    ctx: &Context,
+) -> IResult<&'a str, SpannedValue<'a>> {
+    let start = original.len() - input.len();
+    let header_len = input.find('\n').map_or(input.len(), |end| end + 1);
+    let header = start..start + header_len;
+
+    let prefix = input.as_bytes().first().copied();
+    if let Some(prefix @ (b'*' | b'~' | b'>' | b'%')) = prefix {
+        // null arrays (`*-1`) are handled by `value`
+        if let Ok((mut rest, Length::Count(length))) = length_header(&input[1..], false, ctx) {
+            let _guard = DepthGuard::enter(input)?;
+            let count = length.saturating_mul(if prefix == b'%' { 2 } else { 1 });
+            // the length comes from the input, so it can't be trusted to preallocate
+            let mut children = Vec::with_capacity(count.min(1024));
+            for _ in 0..count {
+                let (remaining, child) = spanned(original, rest, ctx)?;
+                children.push(child);
+                rest = remaining;
+            }
+            let node = match prefix {
+                b'*' => Value::Array(Vec::new()),
+                b'~' => Value::Set(Default::default()),
+                b'>' => Value::Pushes(Vec::new()),
+                _ => Value::Map(Vec::new(), Vec::new()),
+            };
+            let end = original.len() - rest.len();
+            return Ok((
+                rest,
+                SpannedValue {
+                    node,
+                    span: start..end,
+                    header,
+                    children,
+                },
+            ));
+        }
+    }
+
+    let (rest, node) = crate::value(input, ctx)?;
+    let end = original.len() - rest.len();
+    Ok((
+        rest,
+        SpannedValue {
+            node,
+            span: start..end,
+            header: start..header.end.min(end),
+            children: Vec::new(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spanned_scalars() {
+        let (rest, spanned) = parse_value_spanned("$5\r\nhello\r\n+OK\r\n").unwrap();
+        assert_eq!(rest, "+OK\r\n");
+        assert_eq!(spanned.span, 0..11);
+        assert_eq!(spanned.header, 0..4);
+        assert!(spanned.children.is_empty());
+
+        let (_, spanned) = parse_value_spanned("*-1\r\n").unwrap();
+        assert_eq!(spanned.value(), Value::Null);
+        assert_eq!(spanned.span, 0..5);
+        assert_eq!(spanned.header, 0..5);
+    }
+
+    #[test]
+    fn test_spanned_aggregates() {
+        let message = "%2\r\n+a\r\n~2\r\n:2\r\n:1\r\n+b\r\n*0\r\n";
+        let (rest, spanned) = parse_value_spanned(message).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(spanned.value(), crate::parse(message).unwrap());
+        assert_eq!(spanned.clone().into_value(), crate::parse(message).unwrap());
+        assert_eq!(spanned.span, 0..message.len());
+        assert_eq!(
+            spanned
+                .children
+                .iter()
+                .map(|child| child.raw(message))
+                .collect::<Vec<_>>(),
+            vec!["+a\r\n", "~2\r\n:2\r\n:1\r\n", "+b\r\n", "*0\r\n"]
+        );
+        // the children of sets keep the order of the input
+        let set = &spanned.children[1];
+        assert_eq!(set.header, 8..12);
+        assert_eq!(set.children[0].value(), Value::Integer(2));
+        assert_eq!(set.children[0].span, 12..16);
+    }
+
+    #[test]
+    fn test_spanned_with_options() {
+        let strict = ParseOptions {
+            strict_lengths: true,
+            ..ParseOptions::default()
+        };
+        assert!(parse_value_spanned("*01\r\n:1\r\n").is_ok());
+        assert!(parse_value_spanned_with("*01\r\n:1\r\n", &strict).is_err());
+        assert!(parse_value_spanned_with("*1\r\n$01\r\na\r\n", &strict).is_err());
+    }
+
+    #[test]
+    fn test_spanned_error_position() {
+        let message = "*2\r\n:1\r\n?\r\n";
+        let Err(nom::Err::Error(error)) = parse_value_spanned(message) else {
+            panic!("expected an error");
+        };
+        assert_eq!(message.len() - error.input.len(), 8);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{OwnedValue, VerbatimFormat};
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::{btree_set, vec},
+    prelude::*,
+    strategy::BoxedStrategy,
+};
+
+/// Generates the content of simple strings and simple errors (which can't contain CR or LF)
+pub fn arb_simple_string() -> impl Strategy<Value = String> {
+    "[^\r\n]*"
+}
+
+/// Generates the content of big numbers (digits with an optional sign)
+pub fn arb_big_number() -> impl Strategy<Value = String> {
+    "[+-]?[0-9]{1,60}"
+}
+
+/// Generates doubles formatted the way the parser represents them (including `inf`, `-inf` and `NaN`)
+pub fn arb_double() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<f64>(),
+        Just(f64::INFINITY),
+        Just(f64::NEG_INFINITY),
+        Just(f64::NAN),
+    ]
+    .prop_map(|d| d.to_string())
+}
+
+impl Arbitrary for VerbatimFormat {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(VerbatimFormat::Txt),
+            Just(VerbatimFormat::Mkd),
+            // 3 printable ASCII characters, excluding the `:` separator
+            "[!-9;-~]{3}".prop_map(|prefix| VerbatimFormat::from_prefix(&prefix).unwrap()),
+        ]
+        .boxed()
+    }
+}
+
+/// Generates the values that don't contain other values
+pub fn arb_scalar() -> impl Strategy<Value = OwnedValue> {
+    prop_oneof![
+        arb_simple_string().prop_map(OwnedValue::SimpleString),
+        arb_simple_string().prop_map(OwnedValue::SimpleError),
+        any::<i64>().prop_map(OwnedValue::Integer),
+        any::<String>().prop_map(OwnedValue::BulkString),
+        Just(OwnedValue::Null),
+        any::<bool>().prop_map(OwnedValue::Boolean),
+        arb_double().prop_map(OwnedValue::Double),
+        arb_big_number().prop_map(OwnedValue::BigNumber),
+        any::<String>().prop_map(OwnedValue::BulkError),
+        (any::<VerbatimFormat>(), any::<String>())
+            .prop_map(|(format, s)| OwnedValue::VerbatimString(format, s)),
+    ]
+}
+
+/// Generates well-formed values nesting aggregate types up to `depth` levels,
+/// with at most `size` elements in every aggregate.
+///
+/// Pushes are only generated as the outermost value, as servers never nest them.
+///
+/// Every generated value can be encoded and parsed back (with [Value::encode](crate::Value::encode)
+/// and [parse_value](crate::parse_value)) giving the same value.
+///
+/// # Example
+///
+/// ```
+/// use proptest::prelude::*;
+/// use tinyresp::{arb_value, parse_value};
+///
+/// proptest!(|(value in arb_value(3, 4))| {
+///     let encoded = String::from_utf8(value.as_value().encode()).unwrap();
+///     prop_assert_eq!(parse_value(&encoded).unwrap().1, value.as_value());
+/// });
+/// ```
+pub fn arb_value(depth: u32, size: usize) -> BoxedStrategy<OwnedValue> {
+    if depth == 0 {
+        return arb_nested(0, size);
+    }
+    prop_oneof![
+        3 => arb_nested(depth, size),
+        1 => vec(arb_nested(depth - 1, size), 0..=size).prop_map(OwnedValue::Pushes),
+    ]
+    .boxed()
+}
+
+/// Generates values nesting every aggregate type but pushes
+fn arb_nested(depth: u32, size: usize) -> BoxedStrategy<OwnedValue> {
+    arb_scalar()
+        .prop_recursive(
+            depth,
+            (size * depth as usize) as u32,
+            size as u32,
+            move |inner| {
+                prop_oneof![
+                    vec(inner.clone(), 0..=size).prop_map(OwnedValue::Array),
+                    vec((inner.clone(), inner.clone()), 0..=size).prop_map(|entries| {
+                        let (keys, values) = entries.into_iter().unzip();
+                        OwnedValue::Map(keys, values)
+                    }),
+                    btree_set(inner, 0..=size).prop_map(OwnedValue::Set),
+                ]
+            },
+        )
+        .boxed()
+}
+
+/// Generates well-formed values with up to 4 levels of nesting and up to 8 elements per aggregate
+/// (see [arb_value] to customize these limits)
+impl Arbitrary for OwnedValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_value(4, 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{frame_len, parse_value, parse_value_spanned, Decoder, Protocol, ToResp};
+
+    /// Checks that none of the values nested in the value is a push
+    fn no_nested_pushes(value: &OwnedValue) -> bool {
+        let children: Vec<&OwnedValue> = match value {
+            OwnedValue::Array(items) | OwnedValue::Pushes(items) => items.iter().collect(),
+            OwnedValue::Set(items) => items.iter().collect(),
+            OwnedValue::Map(keys, values) => keys.iter().chain(values).collect(),
+            _ => vec![],
+        };
+        children
+            .into_iter()
+            .all(|child| !matches!(child, OwnedValue::Pushes(_)) && no_nested_pushes(child))
+    }
+
+    proptest! {
+        #[test]
+        fn test_pushes_only_at_the_root(value in any::<OwnedValue>()) {
+            prop_assert!(no_nested_pushes(&value));
+        }
+
+        #[test]
+        fn test_roundtrip(value in any::<OwnedValue>()) {
+            let encoded = String::from_utf8(value.as_value().encode()).unwrap();
+            let (rest, parsed) = parse_value(&encoded).unwrap();
+            prop_assert_eq!(rest, "");
+            prop_assert_eq!(OwnedValue::from(&parsed), value);
+        }
+
+        #[test]
+        fn test_frame_len_and_spans(value in any::<OwnedValue>()) {
+            let encoded = String::from_utf8(value.as_value().encode()).unwrap();
+            prop_assert_eq!(frame_len(encoded.as_bytes()), Ok(Some(encoded.len())));
+            let (_, spanned) = parse_value_spanned(&encoded).unwrap();
+            prop_assert_eq!(spanned.span.clone(), 0..encoded.len());
+            prop_assert_eq!(spanned.into_value(), value.as_value());
+        }
+
+        #[test]
+        fn test_truncated(value in any::<OwnedValue>(), cut in any::<prop::sample::Index>()) {
+            let encoded = value.as_value().encode();
+            let len = cut.index(encoded.len());
+            // the truncated encoding may not be valid UTF-8, `frame_len` doesn't care
+            prop_assert_eq!(frame_len(&encoded[..len]), Ok(None));
+            if let Ok(truncated) = std::str::from_utf8(&encoded[..len]) {
+                prop_assert!(parse_value(truncated).is_err());
+            }
+        }
+
+        #[test]
+        fn test_decoder_in_chunks(values in vec(any::<OwnedValue>(), 1..4), size in 1..64usize) {
+            let encoded: Vec<u8> = values.iter().flat_map(|value| value.as_value().encode()).collect();
+            let mut decoder = Decoder::new();
+            let mut decoded = Vec::new();
+            for chunk in encoded.chunks(size) {
+                decoded.extend(decoder.feed(chunk).unwrap());
+            }
+            prop_assert_eq!(decoded, values);
+            prop_assert!(!decoder.in_frame());
+        }
+
+        #[test]
+        fn test_resp2_encoding_is_parsable(value in any::<OwnedValue>()) {
+            let encoded = String::from_utf8(value.as_value().to_resp(Protocol::Resp2)).unwrap();
+            let (rest, _) = parse_value(&encoded).unwrap();
+            prop_assert_eq!(rest, "");
+        }
+    }
+}
@@ -0,0 +1,511 @@
+use crate::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// The version of the RESP protocol used to encode values.
+///
+/// RESP3 introduced new types (maps, sets, doubles, booleans, ...) that RESP2 clients don't understand,
+/// so [ToResp] implementations fall back to the closest RESP2 representation when encoding for [Protocol::Resp2]
+/// (e.g. maps become flat arrays of alternating keys and values and doubles become bulk strings).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub enum Protocol {
+    Resp2,
+    #[default]
+    Resp3,
+}
+
+/// A trait for types that can be encoded as RESP.
+///
+/// Implementations are provided for the primitive types, strings, byte slices, the most common
+/// collections, tuples and for [Value] itself.
+///
+/// Slices (and `Vec`s) are encoded as arrays, with two exceptions:
+/// - `[u8]` (and `Vec<u8>`) is encoded as a bulk string
+/// - `[(K, V)]` (and `Vec<(K, V)>`) is encoded as a map (or as a flat array of alternating keys and
+///   values in RESP2)
+///
+/// # Example
+///
+/// ```
+/// use tinyresp::{Protocol, ToResp};
+///
+/// let scores = vec![("alice".to_string(), 1.5), ("bob".to_string(), 2.0)];
+/// assert_eq!(
+///     scores.to_resp(Protocol::Resp3),
+///     b"%2\r\n$5\r\nalice\r\n,1.5\r\n$3\r\nbob\r\n,2\r\n"
+/// );
+/// assert_eq!(
+///     scores.to_resp(Protocol::Resp2),
+///     b"*4\r\n$5\r\nalice\r\n$3\r\n1.5\r\n$3\r\nbob\r\n$1\r\n2\r\n"
+/// );
+/// ```
+pub trait ToResp {
+    /// Appends the RESP encoding of the current value to the given buffer
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol);
+
+    /// Appends the RESP encoding of a slice of values to the given buffer.
+    /// By default slices are encoded as arrays, implementations can override this method
+    /// to provide a more appropriate encoding (e.g. bytes are encoded as a bulk string).
+    fn write_resp_slice(items: &[Self], out: &mut Vec<u8>, protocol: Protocol)
+    where
+        Self: Sized,
+    {
+        write_header(out, b'*', items.len());
+        for item in items {
+            item.write_resp(out, protocol);
+        }
+    }
+
+    /// Returns the RESP encoding of the current value
+    fn to_resp(&self, protocol: Protocol) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_resp(&mut out, protocol);
+        out
+    }
+}
+
+/// Writes the header of an aggregate or bulk type (e.g. `*3\r\n`)
+pub(crate) fn write_header(out: &mut Vec<u8>, prefix: u8, len: usize) {
+    out.push(prefix);
+    out.extend_from_slice(len.to_string().as_bytes());
+    out.extend_from_slice(b"\r\n");
+}
+
+/// Writes a simple line type (e.g. `+OK\r\n`)
+pub(crate) fn write_line(out: &mut Vec<u8>, prefix: u8, line: &[u8]) {
+    out.push(prefix);
+    out.extend_from_slice(line);
+    out.extend_from_slice(b"\r\n");
+}
+
+/// Writes a bulk type (e.g. `$5\r\nhello\r\n`)
+pub(crate) fn write_bulk(out: &mut Vec<u8>, prefix: u8, data: &[u8]) {
+    write_header(out, prefix, data.len());
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\r\n");
+}
+
+/// Formats a double the way RESP expects it (`inf`, `-inf` and `nan` for the special values)
+pub(crate) fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_null(out: &mut Vec<u8>, protocol: Protocol) {
+    match protocol {
+        Protocol::Resp2 => out.extend_from_slice(b"$-1\r\n"),
+        Protocol::Resp3 => out.extend_from_slice(b"_\r\n"),
+    }
+}
+
+fn write_map<'a, K, V, I>(out: &mut Vec<u8>, protocol: Protocol, len: usize, entries: I)
+where
+    K: ToResp + ?Sized + 'a,
+    V: ToResp + ?Sized + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    match protocol {
+        Protocol::Resp2 => write_header(out, b'*', len * 2),
+        Protocol::Resp3 => write_header(out, b'%', len),
+    }
+    for (key, value) in entries {
+        key.write_resp(out, protocol);
+        value.write_resp(out, protocol);
+    }
+}
+
+fn write_set<'a, T, I>(out: &mut Vec<u8>, protocol: Protocol, len: usize, items: I)
+where
+    T: ToResp + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    match protocol {
+        Protocol::Resp2 => write_header(out, b'*', len),
+        Protocol::Resp3 => write_header(out, b'~', len),
+    }
+    for item in items {
+        item.write_resp(out, protocol);
+    }
+}
+
+impl ToResp for Value<'_> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        match (self, protocol) {
+            // simple strings and errors can't contain CR or LF, so they fall back to the bulk types
+            (Value::SimpleString(s), _) if s.contains(['\r', '\n']) => {
+                write_bulk(out, b'$', s.as_bytes())
+            }
+            (Value::SimpleString(s), _) => write_line(out, b'+', s.as_bytes()),
+            (Value::SimpleError(s), Protocol::Resp2) if s.contains(['\r', '\n']) => {
+                write_line(out, b'-', s.replace(['\r', '\n'], " ").as_bytes())
+            }
+            (Value::SimpleError(s), Protocol::Resp3) if s.contains(['\r', '\n']) => {
+                write_bulk(out, b'!', s.as_bytes())
+            }
+            (Value::SimpleError(s), _) => write_line(out, b'-', s.as_bytes()),
+            (Value::Integer(i), _) => write_line(out, b':', i.to_string().as_bytes()),
+            (Value::BulkString(s), _) => write_bulk(out, b'$', s.as_bytes()),
+            (Value::BulkBytes(bytes), _) => write_bulk(out, b'$', bytes),
+            (Value::Array(values), _) => Value::write_resp_slice(values, out, protocol),
+            (Value::Null, _) => write_null(out, protocol),
+            (Value::Boolean(b), Protocol::Resp2) => {
+                write_line(out, b':', if *b { b"1" } else { b"0" })
+            }
+            (Value::Boolean(b), Protocol::Resp3) => {
+                write_line(out, b'#', if *b { b"t" } else { b"f" })
+            }
+            (Value::Double(s), _) => match s.parse::<f64>() {
+                Ok(d) => d.write_resp(out, protocol),
+                Err(_) => write_bulk(out, b'$', s.as_bytes()),
+            },
+            (Value::BigNumber(s), Protocol::Resp2) => write_bulk(out, b'$', s.as_bytes()),
+            (Value::BigNumber(s), Protocol::Resp3) => write_line(out, b'(', s.as_bytes()),
+            (Value::BulkError(s), Protocol::Resp2) => {
+                // RESP2 only supports errors on a single line
+                write_line(out, b'-', s.replace(['\r', '\n'], " ").as_bytes())
+            }
+            (Value::BulkError(s), Protocol::Resp3) => write_bulk(out, b'!', s.as_bytes()),
+            (Value::VerbatimString(_, s), Protocol::Resp2) => write_bulk(out, b'$', s.as_bytes()),
+            (Value::VerbatimString(format, s), Protocol::Resp3) => {
+                write_header(out, b'=', s.len() + 4);
+                out.extend_from_slice(&format.as_bytes());
+                out.push(b':');
+                out.extend_from_slice(s.as_bytes());
+                out.extend_from_slice(b"\r\n");
+            }
+            (Value::Map(keys, values), _) => {
+                // the keys or the values left without a pair are not written
+                let len = keys.len().min(values.len());
+                write_map(out, protocol, len, keys.iter().zip(values.iter()))
+            }
+            (Value::Set(values), _) => write_set(out, protocol, values.len(), values),
+            (Value::Pushes(values), Protocol::Resp2) => {
+                Value::write_resp_slice(values, out, protocol)
+            }
+            (Value::Pushes(values), Protocol::Resp3) => {
+                write_header(out, b'>', values.len());
+                for value in values {
+                    value.write_resp(out, protocol);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Helper method that encodes the current value as RESP3.
+    /// Use [ToResp::to_resp] if you need to encode the value for a RESP2 peer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tinyresp::{parse, Value};
+    ///
+    /// let value = Value::Array(vec![Value::BulkString("hello"), Value::Integer(42)]);
+    /// let encoded = value.encode();
+    /// assert_eq!(encoded, b"*2\r\n$5\r\nhello\r\n:42\r\n");
+    /// assert_eq!(parse(std::str::from_utf8(&encoded).unwrap()).unwrap(), value);
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_resp(Protocol::Resp3)
+    }
+}
+
+impl<T: ToResp + ?Sized> ToResp for &T {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        (**self).write_resp(out, protocol)
+    }
+}
+
+macro_rules! impl_to_resp_integer {
+    ($($t:ty),*) => {
+        $(
+            impl ToResp for $t {
+                fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+                    match (i64::try_from(*self), protocol) {
+                        (Ok(i), _) => write_line(out, b':', i.to_string().as_bytes()),
+                        // values that don't fit into an i64 are encoded as big numbers
+                        (Err(_), Protocol::Resp2) => write_bulk(out, b'$', self.to_string().as_bytes()),
+                        (Err(_), Protocol::Resp3) => write_line(out, b'(', self.to_string().as_bytes()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_to_resp_integer!(i8, i16, i32, i64, i128, isize, u16, u32, u64, u128, usize);
+
+impl ToResp for u8 {
+    fn write_resp(&self, out: &mut Vec<u8>, _protocol: Protocol) {
+        write_line(out, b':', self.to_string().as_bytes())
+    }
+
+    fn write_resp_slice(items: &[Self], out: &mut Vec<u8>, _protocol: Protocol) {
+        write_bulk(out, b'$', items)
+    }
+}
+
+macro_rules! impl_to_resp_float {
+    ($($t:ty),*) => {
+        $(
+            impl ToResp for $t {
+                fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+                    let formatted = format_double(*self as f64);
+                    match protocol {
+                        Protocol::Resp2 => write_bulk(out, b'$', formatted.as_bytes()),
+                        Protocol::Resp3 => write_line(out, b',', formatted.as_bytes()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_to_resp_float!(f32, f64);
+
+impl ToResp for bool {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        Value::Boolean(*self).write_resp(out, protocol)
+    }
+}
+
+impl ToResp for str {
+    fn write_resp(&self, out: &mut Vec<u8>, _protocol: Protocol) {
+        write_bulk(out, b'$', self.as_bytes())
+    }
+}
+
+impl ToResp for String {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        self.as_str().write_resp(out, protocol)
+    }
+}
+
+impl<T: ToResp> ToResp for Option<T> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        match self {
+            Some(value) => value.write_resp(out, protocol),
+            None => write_null(out, protocol),
+        }
+    }
+}
+
+impl<T: ToResp> ToResp for [T] {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        T::write_resp_slice(self, out, protocol)
+    }
+}
+
+impl<T: ToResp, const N: usize> ToResp for [T; N] {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        T::write_resp_slice(self, out, protocol)
+    }
+}
+
+impl<T: ToResp> ToResp for Vec<T> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        T::write_resp_slice(self, out, protocol)
+    }
+}
+
+impl<T: ToResp> ToResp for BTreeSet<T> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        write_set(out, protocol, self.len(), self)
+    }
+}
+
+impl<T: ToResp, S> ToResp for HashSet<T, S> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        write_set(out, protocol, self.len(), self)
+    }
+}
+
+impl<K: ToResp, V: ToResp, S> ToResp for HashMap<K, V, S> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        write_map(out, protocol, self.len(), self)
+    }
+}
+
+impl<K: ToResp, V: ToResp> ToResp for BTreeMap<K, V> {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        write_map(out, protocol, self.len(), self)
+    }
+}
+
+macro_rules! impl_to_resp_tuple {
+    ($len:expr => $($name:ident $idx:tt),+) => {
+        impl<$($name: ToResp),+> ToResp for ($($name,)+) {
+            fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+                write_header(out, b'*', $len);
+                $(self.$idx.write_resp(out, protocol);)+
+            }
+        }
+    };
+}
+
+impl_to_resp_tuple!(1 => A 0);
+impl<A: ToResp, B: ToResp> ToResp for (A, B) {
+    fn write_resp(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        write_header(out, b'*', 2);
+        self.0.write_resp(out, protocol);
+        self.1.write_resp(out, protocol);
+    }
+
+    fn write_resp_slice(items: &[Self], out: &mut Vec<u8>, protocol: Protocol) {
+        write_map(
+            out,
+            protocol,
+            items.len(),
+            items.iter().map(|(key, value)| (key, value)),
+        )
+    }
+}
+impl_to_resp_tuple!(3 => A 0, B 1, C 2);
+impl_to_resp_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_to_resp_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_to_resp_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, VerbatimFormat};
+
+    fn resp2<T: ToResp + ?Sized>(value: &T) -> String {
+        String::from_utf8(value.to_resp(Protocol::Resp2)).unwrap()
+    }
+
+    fn resp3<T: ToResp + ?Sized>(value: &T) -> String {
+        String::from_utf8(value.to_resp(Protocol::Resp3)).unwrap()
+    }
+
+    #[test]
+    fn test_value_roundtrip() {
+        let messages = [
+            "+OK\r\n",
+            "-ERR boom\r\n",
+            ":-42\r\n",
+            "$5\r\nhello\r\n",
+            "*2\r\n$5\r\nhello\r\n_\r\n",
+            "#t\r\n",
+            ",1.5\r\n",
+            ",inf\r\n",
+            "(-3492890328409238509324850943850943825024385\r\n",
+            "!21\r\nSYNTAX invalid syntax\r\n",
+            "=15\r\ntxt:Some string\r\n",
+            "%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n",
+            "~2\r\n:1\r\n:2\r\n",
+            ">2\r\n+message\r\n:1\r\n",
+        ];
+        for message in messages {
+            let value = parse(message).unwrap();
+            assert_eq!(String::from_utf8(value.encode()).unwrap(), message);
+        }
+        assert_eq!(resp3(&Value::Double("NaN".to_string())), ",nan\r\n");
+
+        // CR and LF can't be written in simple strings and errors
+        assert_eq!(resp3(&Value::SimpleString("a\r\nb")), "$4\r\na\r\nb\r\n");
+        assert_eq!(resp2(&Value::SimpleString("a\nb")), "$3\r\na\nb\r\n");
+        assert_eq!(
+            resp3(&Value::SimpleError("ERR\nboom")),
+            "!8\r\nERR\nboom\r\n"
+        );
+    }
+
+    #[test]
+    fn test_value_resp2() {
+        assert_eq!(resp2(&Value::Null), "$-1\r\n");
+        assert_eq!(resp2(&Value::Boolean(false)), ":0\r\n");
+        assert_eq!(resp2(&Value::Double("1.5".to_string())), "$3\r\n1.5\r\n");
+        assert_eq!(resp2(&Value::BigNumber("123")), "$3\r\n123\r\n");
+        assert_eq!(resp2(&Value::BulkError("ERR\r\nboom")), "-ERR  boom\r\n");
+        assert_eq!(resp2(&Value::SimpleError("ERR\nboom")), "-ERR boom\r\n");
+        assert_eq!(
+            resp2(&Value::VerbatimString(VerbatimFormat::Txt, "hello")),
+            "$5\r\nhello\r\n"
+        );
+        assert_eq!(
+            resp2(&parse("%1\r\n+a\r\n:1\r\n").unwrap()),
+            "*2\r\n+a\r\n:1\r\n"
+        );
+        assert_eq!(resp2(&parse("~1\r\n:1\r\n").unwrap()), "*1\r\n:1\r\n");
+        assert_eq!(resp2(&parse(">1\r\n:1\r\n").unwrap()), "*1\r\n:1\r\n");
+    }
+
+    #[test]
+    fn test_unpaired_map_entries() {
+        let map = Value::Map(
+            vec![Value::SimpleString("a"), Value::SimpleString("b")],
+            vec![Value::Integer(1)],
+        );
+        assert_eq!(resp3(&map), "%1\r\n+a\r\n:1\r\n");
+        assert_eq!(resp2(&map), "*2\r\n+a\r\n:1\r\n");
+        let map = Value::Map(vec![], vec![Value::Integer(1)]);
+        assert_eq!(resp3(&map), "%0\r\n");
+    }
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(resp3(&42i32), ":42\r\n");
+        assert_eq!(resp3(&42u8), ":42\r\n");
+        assert_eq!(resp3(&u64::MAX), "(18446744073709551615\r\n");
+        assert_eq!(resp2(&u64::MAX), "$20\r\n18446744073709551615\r\n");
+        assert_eq!(resp3(&1.5f64), ",1.5\r\n");
+        assert_eq!(resp3(&f64::NEG_INFINITY), ",-inf\r\n");
+        assert_eq!(resp2(&2.0f32), "$1\r\n2\r\n");
+        assert_eq!(resp3(&true), "#t\r\n");
+        assert_eq!(resp2(&true), ":1\r\n");
+    }
+
+    #[test]
+    fn test_strings_and_bytes() {
+        assert_eq!(resp3("hello"), "$5\r\nhello\r\n");
+        assert_eq!(resp3(&"hello".to_string()), "$5\r\nhello\r\n");
+        assert_eq!(resp3(&&"hello"), "$5\r\nhello\r\n");
+        assert_eq!(
+            (b"\xff\x00" as &[u8]).to_resp(Protocol::Resp3),
+            b"$2\r\n\xff\x00\r\n"
+        );
+        assert_eq!(resp3(&b"hi".to_vec()), "$2\r\nhi\r\n");
+        assert_eq!(resp3(b"hi"), "$2\r\nhi\r\n");
+    }
+
+    #[test]
+    fn test_option() {
+        assert_eq!(resp3(&Some(1)), ":1\r\n");
+        assert_eq!(resp3(&None::<i64>), "_\r\n");
+        assert_eq!(resp2(&None::<i64>), "$-1\r\n");
+    }
+
+    #[test]
+    fn test_collections() {
+        assert_eq!(resp3(&vec![1, 2]), "*2\r\n:1\r\n:2\r\n");
+        assert_eq!(resp3(&[1i64, 2][..]), "*2\r\n:1\r\n:2\r\n");
+        assert_eq!(resp3(&BTreeSet::from([1, 2])), "~2\r\n:1\r\n:2\r\n");
+        assert_eq!(resp2(&BTreeSet::from([1, 2])), "*2\r\n:1\r\n:2\r\n");
+        assert_eq!(resp3(&HashSet::from([1])), "~1\r\n:1\r\n");
+        assert_eq!(resp3(&HashMap::from([("a", 1)])), "%1\r\n$1\r\na\r\n:1\r\n");
+        assert_eq!(
+            resp2(&BTreeMap::from([("a", 1), ("b", 2)])),
+            "*4\r\n$1\r\na\r\n:1\r\n$1\r\nb\r\n:2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_tuples() {
+        assert_eq!(resp3(&("a", 1)), "*2\r\n$1\r\na\r\n:1\r\n");
+        assert_eq!(resp3(&("a", 1, true)), "*3\r\n$1\r\na\r\n:1\r\n#t\r\n");
+
+        // slices of pairs are encoded as maps
+        let pairs = vec![("a".to_string(), 1.5), ("b".to_string(), 2.0)];
+        assert_eq!(resp3(&pairs), "%2\r\n$1\r\na\r\n,1.5\r\n$1\r\nb\r\n,2\r\n");
+        assert_eq!(
+            resp2(&pairs),
+            "*4\r\n$1\r\na\r\n$3\r\n1.5\r\n$1\r\nb\r\n$1\r\n2\r\n"
+        );
+    }
+}
@@ -2,7 +2,11 @@ use std::collections::{BTreeSet, HashMap};
 use thiserror::Error;
 
 /// Represents a RESP value
+///
+/// New variants may be added (e.g. [Value::BulkBytes] for the bulk strings that are not valid UTF-8),
+/// so matches on values must have a wildcard arm.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[non_exhaustive]
 pub enum Value<'a> {
     SimpleString(&'a str),
     SimpleError(&'a str),
@@ -14,11 +18,100 @@ pub enum Value<'a> {
     Double(String),
     BigNumber(&'a str),
     BulkError(&'a str),
-    VerbatimString(&'a str, &'a str),
+    /// Verbatim strings are represented as a tuple containing the format of the string and its content
+    VerbatimString(VerbatimFormat, &'a str),
     /// Maps are represented as a tuple of two vectors, the first one contains the keys and the second one the values
     Map(Vec<Value<'a>>, Vec<Value<'a>>),
     Set(BTreeSet<Value<'a>>),
     Pushes(Vec<Value<'a>>),
+    /// A bulk string whose content is not valid UTF-8 (e.g. a binary value or the output of `DUMP`).
+    /// It's only produced by the decoders working on bytes ([Decoder](crate::Decoder) and the clients).
+    BulkBytes(&'a [u8]),
+}
+
+/// Represents the format of a [Value::VerbatimString]
+///
+/// The RESP specification defines the format as exactly 3 bytes followed by a `:` separator.
+/// Redis currently only uses `txt` (plain text) and `mkd` (markdown), every other format is
+/// preserved as-is in the [VerbatimFormat::Other] variant.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum VerbatimFormat {
+    /// Plain text (`txt`)
+    Txt,
+    /// Markdown (`mkd`)
+    Mkd,
+    /// Any other 3 bytes format
+    Other(OtherFormat),
+}
+
+/// The 3 bytes of a [VerbatimFormat::Other] format.
+///
+/// It can only be created through [VerbatimFormat::from_prefix], which guarantees that it's made of
+/// printable ASCII characters.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct OtherFormat([u8; 3]);
+
+impl OtherFormat {
+    /// Returns the 3 characters of the format
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("formats are validated as ASCII when created")
+    }
+
+    /// Returns the 3 bytes of the format
+    pub fn as_bytes(&self) -> [u8; 3] {
+        self.0
+    }
+}
+
+impl VerbatimFormat {
+    /// Creates a [VerbatimFormat] from the 3 characters prefix of a verbatim string.
+    /// Returns `None` if the prefix is not made of exactly 3 printable ASCII characters
+    /// (excluding the `:` separator).
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        let bytes: [u8; 3] = prefix.as_bytes().try_into().ok()?;
+        if !bytes.iter().all(|b| b.is_ascii_graphic() && *b != b':') {
+            return None;
+        }
+        Some(match &bytes {
+            b"txt" => VerbatimFormat::Txt,
+            b"mkd" => VerbatimFormat::Mkd,
+            _ => VerbatimFormat::Other(OtherFormat(bytes)),
+        })
+    }
+
+    /// Returns the 3 characters prefix that identifies the format on the wire
+    pub fn as_str(&self) -> &str {
+        match self {
+            VerbatimFormat::Txt => "txt",
+            VerbatimFormat::Mkd => "mkd",
+            VerbatimFormat::Other(format) => format.as_str(),
+        }
+    }
+
+    /// Returns the 3 bytes that identify the format on the wire
+    pub fn as_bytes(&self) -> [u8; 3] {
+        match self {
+            VerbatimFormat::Txt => *b"txt",
+            VerbatimFormat::Mkd => *b"mkd",
+            VerbatimFormat::Other(format) => format.as_bytes(),
+        }
+    }
+
+    /// Helper method to check if the current format is [VerbatimFormat::Txt]
+    pub fn is_txt(&self) -> bool {
+        matches!(self, VerbatimFormat::Txt)
+    }
+
+    /// Helper method to check if the current format is [VerbatimFormat::Mkd]
+    pub fn is_mkd(&self) -> bool {
+        matches!(self, VerbatimFormat::Mkd)
+    }
+}
+
+impl std::fmt::Display for VerbatimFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Represents an error that can occur when trying to convert a [Value] to a HashMap
@@ -51,6 +144,11 @@ impl<'a> Value<'a> {
         matches!(self, Value::BulkString(_))
     }
 
+    /// Helper method to check if the current value is a [Value::BulkBytes]
+    pub fn is_bulk_bytes(&self) -> bool {
+        matches!(self, Value::BulkBytes(_))
+    }
+
     /// Helper method to check if the current value is a [Value::Array]
     pub fn is_array(&self) -> bool {
         matches!(self, Value::Array(_))
@@ -148,6 +246,15 @@ impl<'a> Value<'a> {
         }
     }
 
+    /// Helper method that tries to get the bytes of the current value.
+    /// This will return `Some(&[u8])` for [Value::BulkBytes] and for all the types supported by [Value::as_str].
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::BulkBytes(bytes) => Some(bytes),
+            value => value.as_str().map(str::as_bytes),
+        }
+    }
+
     /// Helper method that tries to get an integer from the current value.
     /// This will return `Some(i64)` for [Value::Integer].
     pub fn as_i64(&self) -> Option<i64> {
@@ -185,6 +292,28 @@ impl<'a> Value<'a> {
         }
     }
 
+    /// Helper method that tries to get the format and the content of the current value.
+    /// This will return `Some((VerbatimFormat, &str))` for [Value::VerbatimString].
+    pub fn as_verbatim(&self) -> Option<(VerbatimFormat, &str)> {
+        match self {
+            Value::VerbatimString(format, s) => Some((*format, s)),
+            _ => None,
+        }
+    }
+
+    /// Helper method that renders a [Value::VerbatimString] so that it can be displayed in a terminal.
+    /// Only text ([VerbatimFormat::Txt]) and markdown ([VerbatimFormat::Mkd]) contents can be rendered:
+    /// their `\r\n` line endings are normalized to `\n` and trailing line endings are removed.
+    /// This will return `None` for any other format or variant.
+    pub fn render_verbatim(&self) -> Option<String> {
+        match self {
+            Value::VerbatimString(VerbatimFormat::Txt | VerbatimFormat::Mkd, s) => {
+                Some(s.replace("\r\n", "\n").trim_end_matches('\n').to_string())
+            }
+            _ => None,
+        }
+    }
+
     /// Helper method that tries to get a map reference from the current value.
     /// This will return `Some((&Vec<Value>, &Vec<Value>)` for [Value::Map].
     pub fn as_map(&self) -> Option<(&Vec<Value<'a>>, &Vec<Value<'a>>)> {
@@ -329,7 +458,7 @@ mod tests {
 
     #[test]
     fn test_is_verbatim_string() {
-        let value = Value::VerbatimString("txt", "hello");
+        let value = Value::VerbatimString(VerbatimFormat::Txt, "hello");
         assert!(value.is_verbatim_string());
 
         // not a verbatim string
@@ -387,7 +516,7 @@ mod tests {
         let value = Value::BigNumber("1234567890");
         assert!(value.is_string_like());
 
-        let value = Value::VerbatimString("txt", "hello");
+        let value = Value::VerbatimString(VerbatimFormat::Txt, "hello");
         assert!(value.is_string_like());
 
         // not a string-like
@@ -435,7 +564,7 @@ mod tests {
         let value = Value::BigNumber("1234567890");
         assert!(value.is_ok());
 
-        let value = Value::VerbatimString("txt", "hello");
+        let value = Value::VerbatimString(VerbatimFormat::Txt, "hello");
         assert!(value.is_ok());
 
         // not an ok
@@ -463,7 +592,7 @@ mod tests {
         let value = Value::BulkError("hello");
         assert_eq!(value.as_str(), Some("hello"));
 
-        let value = Value::VerbatimString("txt", "hello");
+        let value = Value::VerbatimString(VerbatimFormat::Txt, "hello");
         assert_eq!(value.as_str(), Some("hello"));
 
         // not a string-like
@@ -514,6 +643,60 @@ mod tests {
         assert_eq!(value.as_array(), None);
     }
 
+    #[test]
+    fn test_verbatim_format() {
+        assert_eq!(
+            VerbatimFormat::from_prefix("txt"),
+            Some(VerbatimFormat::Txt)
+        );
+        assert_eq!(
+            VerbatimFormat::from_prefix("mkd"),
+            Some(VerbatimFormat::Mkd)
+        );
+        let raw = VerbatimFormat::from_prefix("raw").unwrap();
+        assert!(matches!(raw, VerbatimFormat::Other(format) if format.as_bytes() == *b"raw"));
+        assert_eq!(VerbatimFormat::from_prefix("tx"), None);
+        assert_eq!(VerbatimFormat::from_prefix("txtx"), None);
+        assert_eq!(VerbatimFormat::from_prefix("t:t"), None);
+        assert_eq!(VerbatimFormat::from_prefix("t\nt"), None);
+        assert_eq!(VerbatimFormat::from_prefix("tè"), None);
+
+        assert!(VerbatimFormat::Txt.is_txt());
+        assert!(!VerbatimFormat::Txt.is_mkd());
+        assert!(VerbatimFormat::Mkd.is_mkd());
+        assert_eq!(VerbatimFormat::Txt.as_str(), "txt");
+        assert_eq!(VerbatimFormat::Mkd.as_bytes(), *b"mkd");
+        assert_eq!(raw.as_str(), "raw");
+        assert_eq!(raw.to_string(), "raw");
+    }
+
+    #[test]
+    fn test_as_verbatim() {
+        let value = Value::VerbatimString(VerbatimFormat::Mkd, "# hello");
+        assert_eq!(value.as_verbatim(), Some((VerbatimFormat::Mkd, "# hello")));
+
+        // not a verbatim string
+        let value = Value::SimpleString("hello");
+        assert_eq!(value.as_verbatim(), None);
+    }
+
+    #[test]
+    fn test_render_verbatim() {
+        let value = Value::VerbatimString(VerbatimFormat::Txt, "line 1\r\nline 2\r\n");
+        assert_eq!(value.render_verbatim(), Some("line 1\nline 2".to_string()));
+
+        let value = Value::VerbatimString(VerbatimFormat::Mkd, "# title\n");
+        assert_eq!(value.render_verbatim(), Some("# title".to_string()));
+
+        // unknown format
+        let value = Value::VerbatimString(VerbatimFormat::from_prefix("raw").unwrap(), "hello");
+        assert_eq!(value.render_verbatim(), None);
+
+        // not a verbatim string
+        let value = Value::BulkString("hello");
+        assert_eq!(value.render_verbatim(), None);
+    }
+
     #[test]
     fn test_as_map() {
         let value = Value::Map(vec![], vec![]);
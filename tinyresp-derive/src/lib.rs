@@ -0,0 +1,214 @@
+//! Derive macros for [`tinyresp`](https://crates.io/crates/tinyresp).
+//!
+//! This crate is not meant to be used directly, enable the `derive` feature of `tinyresp` instead.
+//!
+//! # Example
+//!
+//! ```
+//! use tinyresp::{parse, FromResp};
+//!
+//! #[derive(FromResp, Debug, PartialEq)]
+//! struct User<'a> {
+//!     name: &'a str,
+//!     #[resp(rename = "years")]
+//!     age: u32,
+//!     email: Option<String>,
+//! }
+//!
+//! let value = parse("%2\r\n+name\r\n+John\r\n+years\r\n:42\r\n").unwrap();
+//! assert_eq!(
+//!     User::from_resp(&value).unwrap(),
+//!     User { name: "John", age: 42, email: None }
+//! );
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam, Lifetime, LitStr,
+};
+
+/// Derives `tinyresp::FromResp` for a struct.
+///
+/// Structs with named fields are decoded from a RESP3 map or from a RESP2 flat array of alternating
+/// keys and values (like the reply of `HGETALL`). Unknown keys are ignored, missing keys are decoded
+/// from a null value (so that `Option` fields become `None`) and produce an error otherwise.
+/// Tuple structs are decoded positionally from an array.
+///
+/// The following field attributes are supported:
+/// - `#[resp(rename = "name")]`: uses a different key for the field
+/// - `#[resp(default)]`: uses `Default::default()` when the key is missing
+///
+/// If the struct has lifetime parameters, the first one is used as the lifetime of the decoded
+/// [`Value`](https://docs.rs/tinyresp/latest/tinyresp/enum.Value.html), so borrowed fields like
+/// `&'a str` are supported.
+#[proc_macro_derive(FromResp, attributes(resp))]
+pub fn derive_from_resp(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct FieldOptions {
+    rename: Option<String>,
+    default: bool,
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions {
+        rename: None,
+        default: false,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("resp"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                options.rename = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                options.default = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported resp attribute"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "FromResp can only be derived for structs",
+            ))
+        }
+    };
+
+    // reuse the first lifetime of the struct (if any) as the lifetime of the value
+    let lifetime = input
+        .generics
+        .lifetimes()
+        .next()
+        .map(|param| param.lifetime.clone());
+    let mut impl_generics = input.generics.clone();
+    let lifetime = match lifetime {
+        Some(lifetime) => lifetime,
+        None => {
+            let lifetime = Lifetime::new("'__resp", Span::call_site());
+            impl_generics
+                .params
+                .insert(0, GenericParam::Lifetime(parse_quote!(#lifetime)));
+            lifetime
+        }
+    };
+    {
+        let where_clause = impl_generics.make_where_clause();
+        for param in input.generics.type_params() {
+            let ident = &param.ident;
+            where_clause
+                .predicates
+                .push(parse_quote!(#ident: ::tinyresp::FromResp<#lifetime>));
+        }
+    }
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let body = match &data.fields {
+        Fields::Named(fields) => {
+            let mut slots = Vec::new();
+            let mut matches = Vec::new();
+            let mut inits = Vec::new();
+            for (idx, field) in fields.named.iter().enumerate() {
+                let options = field_options(field)?;
+                let ident = field.ident.as_ref().unwrap();
+                let slot = syn::Ident::new(&format!("__field{}", idx), Span::call_site());
+                let key = options
+                    .rename
+                    .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+                let missing = if options.default {
+                    quote!(::core::default::Default::default())
+                } else {
+                    quote! {
+                        ::tinyresp::FromResp::from_resp(&::tinyresp::Value::Null)
+                            .map_err(|_| ::tinyresp::FromRespError::MissingField(#key))?
+                    }
+                };
+
+                slots.push(quote! {
+                    let mut #slot: ::core::option::Option<&::tinyresp::Value<#lifetime>> =
+                        ::core::option::Option::None;
+                });
+                matches.push(quote! {
+                    ::core::option::Option::Some(#key) => #slot = ::core::option::Option::Some(value),
+                });
+                inits.push(quote! {
+                    #ident: match #slot {
+                        ::core::option::Option::Some(value) => ::tinyresp::FromResp::from_resp(value)
+                            .map_err(|e| ::tinyresp::FromRespError::InvalidField {
+                                field: #key,
+                                source: ::std::boxed::Box::new(e),
+                            })?,
+                        ::core::option::Option::None => #missing,
+                    },
+                });
+            }
+            quote! {
+                #(#slots)*
+                for (key, value) in ::tinyresp::map_entries(#name_str, v)? {
+                    match key.as_str() {
+                        #(#matches)*
+                        _ => {}
+                    }
+                }
+                ::core::result::Result::Ok(Self { #(#inits)* })
+            }
+        }
+        Fields::Unnamed(fields) => {
+            let len = fields.unnamed.len();
+            let inits =
+                (0..len).map(|idx| quote!(::tinyresp::FromResp::from_resp(&values[#idx])?,));
+            quote! {
+                match v {
+                    ::tinyresp::Value::Array(values) if values.len() == #len => {
+                        ::core::result::Result::Ok(Self(#(#inits)*))
+                    }
+                    ::tinyresp::Value::Array(values) => {
+                        ::core::result::Result::Err(::tinyresp::FromRespError::WrongLength {
+                            expected: #len,
+                            found: values.len(),
+                        })
+                    }
+                    v => ::core::result::Result::Err(::tinyresp::FromRespError::unexpected(#name_str, v)),
+                }
+            }
+        }
+        Fields::Unit => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "FromResp cannot be derived for unit structs",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::tinyresp::FromResp<#lifetime> for #name #ty_generics #where_clause {
+            fn from_resp(
+                v: &::tinyresp::Value<#lifetime>,
+            ) -> ::core::result::Result<Self, ::tinyresp::FromRespError> {
+                #body
+            }
+        }
+    })
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use tinyresp::{parse, FromResp, FromRespError, Value};
+
+#[derive(FromResp, Debug, PartialEq)]
+struct User {
+    name: String,
+    age: u32,
+    email: Option<String>,
+}
+
+#[derive(FromResp, Debug, PartialEq)]
+struct Borrowed<'a> {
+    #[resp(rename = "user-name")]
+    name: &'a str,
+    #[resp(default)]
+    tags: Vec<&'a str>,
+}
+
+#[derive(FromResp, Debug, PartialEq)]
+struct Generic<T> {
+    value: T,
+    extra: HashMap<String, i64>,
+}
+
+#[derive(FromResp, Debug, PartialEq)]
+struct Pair(String, f64);
+
+#[test]
+fn test_named_fields_from_map() {
+    let value =
+        parse("%3\r\n+name\r\n+John\r\n+age\r\n:42\r\n+email\r\n+john@example.com\r\n").unwrap();
+    assert_eq!(
+        User::from_resp(&value),
+        Ok(User {
+            name: "John".to_string(),
+            age: 42,
+            email: Some("john@example.com".to_string()),
+        })
+    );
+}
+
+#[test]
+fn test_named_fields_from_flat_array() {
+    // RESP2 reply to HGETALL, with an unknown field
+    let value =
+        parse("*6\r\n$4\r\nname\r\n$4\r\nJohn\r\n$3\r\nage\r\n$2\r\n42\r\n$1\r\nx\r\n$1\r\ny\r\n")
+            .unwrap();
+    assert_eq!(
+        User::from_resp(&value),
+        Ok(User {
+            name: "John".to_string(),
+            age: 42,
+            email: None,
+        })
+    );
+}
+
+#[test]
+fn test_missing_and_invalid_fields() {
+    let value = parse("%1\r\n+name\r\n+John\r\n").unwrap();
+    assert_eq!(
+        User::from_resp(&value),
+        Err(FromRespError::MissingField("age"))
+    );
+
+    let value = parse("%2\r\n+name\r\n+John\r\n+age\r\n:-1\r\n").unwrap();
+    assert!(matches!(
+        User::from_resp(&value),
+        Err(FromRespError::InvalidField { field: "age", .. })
+    ));
+
+    assert!(User::from_resp(&Value::Integer(1)).is_err());
+}
+
+#[test]
+fn test_borrowed_fields() {
+    let value = parse("%1\r\n+user-name\r\n+John\r\n").unwrap();
+    assert_eq!(
+        Borrowed::from_resp(&value),
+        Ok(Borrowed {
+            name: "John",
+            tags: vec![],
+        })
+    );
+
+    let value = parse("%2\r\n+user-name\r\n+John\r\n+tags\r\n*1\r\n+admin\r\n").unwrap();
+    assert_eq!(
+        value.decode::<Borrowed>(),
+        Ok(Borrowed {
+            name: "John",
+            tags: vec!["admin"],
+        })
+    );
+}
+
+#[test]
+fn test_generic_struct() {
+    let value = parse("%2\r\n+value\r\n,1.5\r\n+extra\r\n%1\r\n+a\r\n:1\r\n").unwrap();
+    assert_eq!(
+        Generic::<f64>::from_resp(&value),
+        Ok(Generic {
+            value: 1.5,
+            extra: HashMap::from([("a".to_string(), 1)]),
+        })
+    );
+}
+
+#[test]
+fn test_tuple_struct() {
+    let value = parse("*2\r\n$1\r\na\r\n,1.5\r\n").unwrap();
+    assert_eq!(Pair::from_resp(&value), Ok(Pair("a".to_string(), 1.5)));
+
+    let value = parse("*1\r\n$1\r\na\r\n").unwrap();
+    assert_eq!(
+        Pair::from_resp(&value),
+        Err(FromRespError::WrongLength {
+            expected: 2,
+            found: 1
+        })
+    );
+}
@@ -0,0 +1,304 @@
+//! Decodes dumps of RESP traffic (e.g. captured with tcpdump or read from an AOF file).
+//!
+//! The input is split in frames with [`tinyresp::parse_value`], every frame can then be rendered
+//! in a `redis-cli`-like human format, as JSON or as an indented tree showing the type byte and the
+//! length of every value.
+//!
+//! # Example
+//!
+//! ```
+//! use tinyresp_inspect::{decode, render, Format};
+//!
+//! let (frames, malformed) = decode(b"*2\r\n$5\r\nhello\r\n:1\r\n+OK\r\n?");
+//! assert_eq!(frames.len(), 2);
+//! assert_eq!(render(&frames[0], Format::Human), "1) \"hello\"\n2) (integer) 1");
+//! assert_eq!(malformed.unwrap().offset, 24);
+//! ```
+
+use std::fmt;
+use tinyresp::{frame_len, parse_value_with_warnings, ParseOptions, Value, Warning};
+
+/// The format used to render the frames
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// The format used by `redis-cli` (see the `Display` implementation of [Value])
+    Human,
+    /// One JSON document per frame
+    Json,
+    /// An indented tree showing the type byte and the length of every value
+    Tree,
+}
+
+/// A frame decoded from the input
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame<'a> {
+    /// The position of the first byte of the frame in the input
+    pub offset: usize,
+    /// The length of the frame in bytes
+    pub len: usize,
+    pub value: Value<'a>,
+}
+
+/// A frame that could not be decoded
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Malformed {
+    /// The position of the first byte of the frame in the input
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for Malformed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "malformed frame at byte {}: {}",
+            self.offset, self.reason
+        )
+    }
+}
+
+/// Decodes all the frames in the input.
+/// Decoding stops at the first malformed frame, which is returned together with the frames decoded so far.
+pub fn decode(input: &[u8]) -> (Vec<Frame<'_>>, Option<Malformed>) {
+    let (frames, malformed, _) = decode_with(input, &ParseOptions::default());
+    (frames, malformed)
+}
+
+/// Decodes all the frames in the input like [decode] does, using the given parsing options
+/// (e.g. to tolerate the deviations from the RESP specification of non-compliant peers).
+///
+/// The tolerated deviations are returned as warnings, with offsets relative to the start of the input.
+pub fn decode_with<'a>(
+    input: &'a [u8],
+    options: &ParseOptions,
+) -> (Vec<Frame<'a>>, Option<Malformed>, Vec<Warning>) {
+    let (text, invalid_utf8) = match std::str::from_utf8(input) {
+        Ok(text) => (text, None),
+        Err(e) => (
+            // only the valid prefix can be parsed
+            std::str::from_utf8(&input[..e.valid_up_to()]).unwrap(),
+            Some(e.valid_up_to()),
+        ),
+    };
+
+    let mut frames = Vec::new();
+    let mut warnings = Vec::new();
+    let mut rest = text;
+    loop {
+        let offset = text.len() - rest.len();
+        if rest.is_empty() {
+            let malformed = invalid_utf8.map(|position| Malformed {
+                offset,
+                reason: format!("invalid UTF-8 at byte {}", position),
+            });
+            return (frames, malformed, warnings);
+        }
+        let (result, frame_warnings) = parse_value_with_warnings(rest, options);
+        match result {
+            Ok((remaining, value)) => {
+                warnings.extend(frame_warnings.into_iter().map(|warning| Warning {
+                    offset: offset + warning.offset,
+                    ..warning
+                }));
+                frames.push(Frame {
+                    offset,
+                    len: rest.len() - remaining.len(),
+                    value,
+                });
+                rest = remaining;
+            }
+            Err(_) => {
+                let reason = match invalid_utf8 {
+                    Some(position) => format!("invalid UTF-8 at byte {}", position),
+                    None => match frame_len(rest.as_bytes()) {
+                        Ok(None) => "truncated frame".to_string(),
+                        Err(e) => e.to_string(),
+                        Ok(Some(_)) => "invalid frame".to_string(),
+                    },
+                };
+                return (frames, Some(Malformed { offset, reason }), warnings);
+            }
+        }
+    }
+}
+
+/// Renders a frame in the given format (without a trailing newline)
+pub fn render(frame: &Frame<'_>, format: Format) -> String {
+    match format {
+        Format::Human => frame.value.to_string(),
+        Format::Json => frame.value.to_json().to_string(),
+        Format::Tree => {
+            let mut out = format!("frame @ byte {} ({} bytes)", frame.offset, frame.len);
+            tree(&mut out, &frame.value, 1);
+            out
+        }
+    }
+}
+
+fn tree(out: &mut String, value: &Value<'_>, depth: usize) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(depth));
+    match value {
+        Value::SimpleString(s) => out.push_str(&format!("+ {:?}", s)),
+        Value::SimpleError(s) => out.push_str(&format!("- {:?}", s)),
+        Value::Integer(i) => out.push_str(&format!(": {}", i)),
+        Value::BulkString(s) => out.push_str(&format!("${} {:?}", s.len(), s)),
+        Value::Null => out.push('_'),
+        Value::Boolean(b) => out.push_str(&format!("# {}", b)),
+        Value::Double(d) => out.push_str(&format!(", {}", d)),
+        Value::BigNumber(n) => out.push_str(&format!("( {}", n)),
+        Value::BulkError(s) => out.push_str(&format!("!{} {:?}", s.len(), s)),
+        Value::BulkBytes(bytes) => {
+            out.push_str(&format!("${} {}", bytes.len(), bytes.escape_ascii()))
+        }
+        Value::VerbatimString(format, s) => {
+            // the length includes the format and the `:` separator
+            out.push_str(&format!("={} {} {:?}", s.len() + 4, format, s))
+        }
+        Value::Array(values) | Value::Pushes(values) => {
+            let type_byte = if value.is_array() { '*' } else { '>' };
+            out.push_str(&format!("{}{}", type_byte, values.len()));
+            for value in values {
+                tree(out, value, depth + 1);
+            }
+        }
+        Value::Set(values) => {
+            out.push_str(&format!("~{}", values.len()));
+            for value in values {
+                tree(out, value, depth + 1);
+            }
+        }
+        Value::Map(keys, values) => {
+            out.push_str(&format!("%{}", keys.len()));
+            for (key, value) in keys.iter().zip(values) {
+                tree(out, key, depth + 1);
+                tree(out, value, depth + 2);
+            }
+        }
+        // the types added to a later version of tinyresp are shown like redis-cli does
+        value => out.push_str(&value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tinyresp::Leniency;
+
+    #[test]
+    fn test_decode() {
+        let (frames, malformed) = decode(b"+OK\r\n:1\r\n");
+        assert_eq!(
+            frames,
+            vec![
+                Frame {
+                    offset: 0,
+                    len: 5,
+                    value: Value::SimpleString("OK")
+                },
+                Frame {
+                    offset: 5,
+                    len: 4,
+                    value: Value::Integer(1)
+                },
+            ]
+        );
+        assert_eq!(malformed, None);
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        let (frames, malformed) = decode(b"+OK\r\n$5\r\nhel");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(
+            malformed,
+            Some(Malformed {
+                offset: 5,
+                reason: "truncated frame".to_string()
+            })
+        );
+
+        let (frames, malformed) = decode(b"+OK\r\n:1\r\n?what\r\n:2\r\n");
+        assert_eq!(frames.len(), 2);
+        let malformed = malformed.unwrap();
+        assert_eq!(malformed.offset, 9);
+        assert!(malformed
+            .to_string()
+            .starts_with("malformed frame at byte 9: "));
+
+        let (frames, malformed) = decode(b"+OK\r\n$2\r\n\xff\xff\r\n");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(
+            malformed,
+            Some(Malformed {
+                offset: 5,
+                reason: "invalid UTF-8 at byte 9".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_lenient() {
+        let input = b"+OK\r\n*2\n#T\r\n:1\r\n";
+        let (frames, malformed) = decode(input);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(malformed.unwrap().offset, 5);
+
+        let options = ParseOptions {
+            leniency: Leniency::all(),
+            ..ParseOptions::default()
+        };
+        let (frames, malformed, warnings) = decode_with(input, &options);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(malformed, None);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| warning.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line terminated by a bare LF at byte 7",
+                "uppercase boolean at byte 9"
+            ]
+        );
+    }
+
+    fn render_one(input: &str, format: Format) -> String {
+        let (frames, _) = decode(input.as_bytes());
+        render(&frames[0], format)
+    }
+
+    #[test]
+    fn test_render_human() {
+        assert_eq!(
+            render_one("*2\r\n$1\r\na\r\n_\r\n", Format::Human),
+            "1) \"a\"\n2) (nil)"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            render_one(
+                "%2\r\n+a\r\n*2\r\n:1\r\n_\r\n$1\r\nb\r\n-ERR\r\n",
+                Format::Json
+            ),
+            "{\"a\":[1,null],\"b\":{\"error\":\"ERR\"}}"
+        );
+        assert_eq!(
+            render_one("%1\r\n:1\r\n,inf\r\n", Format::Json),
+            "[[1,\"inf\"]]"
+        );
+    }
+
+    #[test]
+    fn test_render_tree() {
+        assert_eq!(
+            render_one(
+                "*3\r\n$5\r\nhello\r\n%1\r\n+key\r\n:1\r\n=9\r\ntxt:hello\r\n",
+                Format::Tree
+            ),
+            "frame @ byte 0 (44 bytes)\n  *3\n    $5 \"hello\"\n    %1\n      + \"key\"\n        : 1\n    =9 txt \"hello\""
+        );
+    }
+}
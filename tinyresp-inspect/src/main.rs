@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+use tinyresp::{Leniency, ParseOptions};
+use tinyresp_inspect::{decode_with, render, Format};
+
+const USAGE: &str = "Usage: resp-inspect [--json | --tree] [--lenient] [FILE]
+
+Decodes the RESP frames in FILE (or in the standard input if FILE is missing or `-`).
+
+Options:
+    --json    Print every frame as JSON (one document per line)
+    --tree    Print every frame as a tree showing the type byte and the length of every value
+    --lenient Tolerate the common deviations from the RESP specification (e.g. bare LF line endings),
+              printing a warning for each of them";
+
+fn main() -> ExitCode {
+    let mut format = Format::Human;
+    let mut options = ParseOptions::default();
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => format = Format::Json,
+            "--tree" => format = Format::Tree,
+            "--lenient" => options.leniency = Leniency::all(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if path.is_none() && (arg == "-" || !arg.starts_with('-')) => path = Some(arg),
+            _ => {
+                eprintln!("unknown argument: {}\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let input = match path.as_deref() {
+        None | Some("-") => {
+            let mut input = Vec::new();
+            io::stdin().read_to_end(&mut input).map(|_| input)
+        }
+        Some(path) => fs::read(path),
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("unable to read the input: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (frames, malformed, warnings) = decode_with(&input, &options);
+    for frame in &frames {
+        println!("{}", render(frame, format));
+    }
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    match malformed {
+        Some(malformed) => {
+            eprintln!("{}", malformed);
+            ExitCode::FAILURE
+        }
+        None => ExitCode::SUCCESS,
+    }
+}
@@ -0,0 +1,56 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run(args: &[&str], input: &[u8]) -> (bool, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_resp-inspect"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_stdin_json() {
+    let (success, stdout, stderr) = run(&["--json"], b"*2\r\n$3\r\nGET\r\n$1\r\na\r\n$-1\r\n");
+    assert!(success);
+    assert_eq!(stdout, "[\"GET\",\"a\"]\nnull\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn test_file_malformed() {
+    let path = std::env::temp_dir().join(format!("resp-inspect-{}.resp", std::process::id()));
+    std::fs::write(&path, b"+OK\r\n:x\r\n").unwrap();
+    let (success, stdout, stderr) = run(&[path.to_str().unwrap()], b"");
+    std::fs::remove_file(&path).unwrap();
+    assert!(!success);
+    assert_eq!(stdout, "OK\n");
+    assert!(stderr.starts_with("malformed frame at byte 5: "));
+}
+
+#[test]
+fn test_lenient() {
+    let input = b"*2\n$5\nhello\n#T\r\n";
+    let (success, _, _) = run(&[], input);
+    assert!(!success);
+
+    let (success, stdout, stderr) = run(&["--lenient"], input);
+    assert!(success);
+    assert_eq!(stdout, "1) \"hello\"\n2) (true)\n");
+    assert_eq!(
+        stderr,
+        "warning: line terminated by a bare LF at byte 2\n\
+         warning: line terminated by a bare LF at byte 5\n\
+         warning: line terminated by a bare LF at byte 11\n\
+         warning: uppercase boolean at byte 13\n"
+    );
+}
@@ -0,0 +1,445 @@
+//! A transparent RESP proxy that logs the decoded traffic.
+//!
+//! Every byte received from a client is forwarded untouched to the upstream server (and vice versa),
+//! while a copy of the traffic is decoded with [`tinyresp`] so that every request/response pair
+//! can be logged together with the time the upstream server took to reply.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::net::TcpListener;
+//! use tinyresp_proxy::{Format, Proxy};
+//!
+//! let proxy = Proxy::new("127.0.0.1:6379", Format::Pretty);
+//! proxy.serve(TcpListener::bind("127.0.0.1:6380").unwrap()).unwrap();
+//! ```
+
+use serde_json::json;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tinyresp::client::{decode_frame, ClientError};
+use tinyresp::OwnedValue;
+
+/// The maximum number of requests waiting for their replies on a connection.
+/// Past this limit the connection is no longer logged (its traffic is still forwarded).
+pub const MAX_PENDING_REQUESTS: usize = 64 * 1024;
+
+/// The format used to log the traffic
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// A human readable multi-line format (values are rendered like `redis-cli` does)
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+/// A request/response pair (or an out-of-band push message) observed by the proxy
+#[derive(Debug, PartialEq, Clone)]
+pub struct Exchange {
+    pub client: SocketAddr,
+    /// The request sent by the client (`None` for push messages)
+    pub request: Option<OwnedValue>,
+    pub reply: OwnedValue,
+    /// The time between the request being forwarded and the reply being received
+    pub latency: Option<Duration>,
+}
+
+impl Exchange {
+    /// Returns the name of the command (in uppercase), if any
+    pub fn command(&self) -> Option<String> {
+        match &self.request {
+            Some(OwnedValue::Array(parts)) => parts
+                .first()
+                .and_then(|name| name.as_value().as_str().map(str::to_ascii_uppercase)),
+            _ => None,
+        }
+    }
+
+    /// Formats the exchange in the given format
+    pub fn format(&self, format: Format) -> String {
+        let command = self.command().unwrap_or_else(|| "PUSH".to_string());
+        let latency_ms = self.latency.map(|latency| latency.as_secs_f64() * 1000.0);
+        match format {
+            Format::Pretty => {
+                let mut out = format!("{} {}", self.client, command);
+                if let Some(latency_ms) = latency_ms {
+                    out.push_str(&format!(" {:.3}ms", latency_ms));
+                }
+                if let Some(request) = &self.request {
+                    out.push_str(&format!("\n>> {}", indented(request)));
+                }
+                out.push_str(&format!("\n<< {}\n", indented(&self.reply)));
+                out
+            }
+            Format::Json => {
+                let mut entry = serde_json::Map::new();
+                entry.insert("client".to_string(), json!(self.client.to_string()));
+                entry.insert("command".to_string(), json!(command));
+                if let Some(latency_ms) = latency_ms {
+                    entry.insert("latency_ms".to_string(), json!(latency_ms));
+                }
+                if let Some(request) = &self.request {
+                    entry.insert("request".to_string(), request.as_value().to_json());
+                }
+                entry.insert("reply".to_string(), self.reply.as_value().to_json());
+                format!("{}\n", serde_json::Value::Object(entry))
+            }
+        }
+    }
+}
+
+/// Renders a value like `redis-cli` does, aligning its lines after the `>> ` and `<< ` markers
+fn indented(value: &OwnedValue) -> String {
+    value.to_string().replace('\n', "\n   ")
+}
+
+/// An error noticed while proxying a connection
+#[derive(Debug)]
+pub enum ProxyError {
+    /// A connection failed
+    Io(io::Error),
+    /// The traffic of a client couldn't be decoded: it's still forwarded but no longer logged
+    Decode {
+        client: SocketAddr,
+        error: ClientError,
+    },
+    /// A client has more than [MAX_PENDING_REQUESTS] requests waiting for their replies:
+    /// its traffic is still forwarded but no longer logged
+    TooManyPending { client: SocketAddr },
+}
+
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyError::Io(e) => write!(f, "connection error: {}", e),
+            ProxyError::Decode { client, error } => {
+                write!(f, "unable to decode the traffic of {}: {}", client, error)
+            }
+            ProxyError::TooManyPending { client } => {
+                write!(f, "too many pending requests from {}", client)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProxyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProxyError::Io(e) => Some(e),
+            ProxyError::Decode { error, .. } => Some(error),
+            ProxyError::TooManyPending { .. } => None,
+        }
+    }
+}
+
+type Log = Arc<Mutex<Box<dyn Write + Send>>>;
+type ErrorHandler = Arc<dyn Fn(&ProxyError) + Send + Sync>;
+
+/// A proxy forwarding every connection to the same upstream server
+pub struct Proxy {
+    upstream: String,
+    format: Format,
+    log: Log,
+    on_error: ErrorHandler,
+}
+
+/// The requests of a connection waiting for their replies
+#[derive(Default)]
+struct Pending {
+    requests: VecDeque<(OwnedValue, Instant)>,
+    /// Set when the requests and the replies can't be paired anymore
+    stopped: bool,
+}
+
+impl Pending {
+    /// Stops logging the connection, reporting the reason (only the first time)
+    fn stop(&mut self, error: ProxyError, on_error: &ErrorHandler) {
+        if !self.stopped {
+            self.stopped = true;
+            self.requests.clear();
+            on_error(&error);
+        }
+    }
+}
+
+impl Proxy {
+    /// Creates a new proxy forwarding to the given upstream server and logging to stdout
+    pub fn new(upstream: impl Into<String>, format: Format) -> Self {
+        Proxy {
+            upstream: upstream.into(),
+            format,
+            log: Arc::new(Mutex::new(Box::new(io::stdout()))),
+            on_error: Arc::new(|_| {}),
+        }
+    }
+
+    /// Calls the given function with the errors of the connections (by default they're ignored)
+    pub fn on_error(mut self, on_error: impl Fn(&ProxyError) + Send + Sync + 'static) -> Self {
+        self.on_error = Arc::new(on_error);
+        self
+    }
+
+    /// Logs the traffic to the given writer instead of stdout
+    pub fn log_to(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.log = Arc::new(Mutex::new(Box::new(writer)));
+        self
+    }
+
+    /// Accepts connections from the given listener, serving each of them in a dedicated thread.
+    /// This function only returns if accepting a connection fails.
+    pub fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let proxy = Arc::new(self);
+        for client in listener.incoming() {
+            let client = client?;
+            let proxy = Arc::clone(&proxy);
+            thread::spawn(move || {
+                if let Err(e) = proxy.handle_connection(client) {
+                    (proxy.on_error)(&ProxyError::Io(e));
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Proxies a single client connection until either side closes it.
+    /// The errors decoding the traffic are reported to the [Proxy::on_error] function.
+    pub fn handle_connection(&self, client: TcpStream) -> io::Result<()> {
+        let client_addr = client.peer_addr()?;
+        let upstream = TcpStream::connect(&self.upstream)?;
+        let pending: Arc<Mutex<Pending>> = Default::default();
+
+        // upstream -> client
+        let replies = {
+            let mut upstream = upstream.try_clone()?;
+            let mut client = client.try_clone()?;
+            let pending = Arc::clone(&pending);
+            let log = Arc::clone(&self.log);
+            let on_error = Arc::clone(&self.on_error);
+            let format = self.format;
+            thread::spawn(move || {
+                let mut decoder = Decoder::default();
+                let result = forward(&mut upstream, &mut client, |chunk| {
+                    for reply in decoder.feed(chunk, false) {
+                        let mut pending = pending.lock().unwrap();
+                        let reply = match reply {
+                            Ok(reply) if !pending.stopped => reply,
+                            Ok(_) => continue,
+                            Err(error) => {
+                                let error = ProxyError::Decode {
+                                    client: client_addr,
+                                    error,
+                                };
+                                pending.stop(error, &on_error);
+                                continue;
+                            }
+                        };
+                        let exchange = match reply {
+                            // over RESP3 the confirmations of (P|S)SUBSCRIBE and (P|S)UNSUBSCRIBE
+                            // are push messages: the first one is paired to the request
+                            OwnedValue::Pushes(ref values)
+                                if !pending.requests.front().is_some_and(|(request, _)| {
+                                    confirms(request, values.first())
+                                }) =>
+                            {
+                                Exchange {
+                                    client: client_addr,
+                                    request: None,
+                                    reply,
+                                    latency: None,
+                                }
+                            }
+                            reply => {
+                                let request = pending.requests.pop_front();
+                                Exchange {
+                                    client: client_addr,
+                                    latency: request.as_ref().map(|(_, start)| start.elapsed()),
+                                    request: request.map(|(request, _)| request),
+                                    reply,
+                                }
+                            }
+                        };
+                        drop(pending);
+                        write_log(&log, &exchange.format(format));
+                    }
+                });
+                let _ = client.shutdown(Shutdown::Write);
+                result
+            })
+        };
+
+        // client -> upstream
+        let mut decoder = Decoder::default();
+        let result = forward(
+            &mut client.try_clone()?,
+            &mut upstream.try_clone()?,
+            |chunk| {
+                let now = Instant::now();
+                let mut pending = pending.lock().unwrap();
+                for request in decoder.feed(chunk, true) {
+                    match request {
+                        Ok(_) if pending.stopped => {}
+                        Ok(_) if pending.requests.len() == MAX_PENDING_REQUESTS => {
+                            let error = ProxyError::TooManyPending {
+                                client: client_addr,
+                            };
+                            pending.stop(error, &self.on_error);
+                        }
+                        Ok(request) => pending.requests.push_back((request, now)),
+                        // the reply to the request can't be paired anymore
+                        Err(error) => {
+                            let error = ProxyError::Decode {
+                                client: client_addr,
+                                error,
+                            };
+                            pending.stop(error, &self.on_error);
+                        }
+                    }
+                }
+            },
+        );
+        let _ = upstream.shutdown(Shutdown::Write);
+        let replies = replies.join().expect("the reply thread panicked");
+        result.and(replies)
+    }
+}
+
+/// Checks if the push message starting with `kind` confirms the given request
+fn confirms(request: &OwnedValue, kind: Option<&OwnedValue>) -> bool {
+    let OwnedValue::Array(parts) = request else {
+        return false;
+    };
+    match (parts.first(), kind) {
+        (Some(name), Some(kind)) => match (name.as_value().as_str(), kind.as_value().as_str()) {
+            (Some(name), Some(kind)) => name.eq_ignore_ascii_case(kind),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn write_log(log: &Log, entry: &str) {
+    let mut log = log.lock().unwrap();
+    let _ = log.write_all(entry.as_bytes());
+    let _ = log.flush();
+}
+
+/// Copies all the bytes from `from` to `to`, calling `inspect` with every chunk before forwarding it
+/// (so that a request is always queued before the upstream server can reply to it)
+fn forward(
+    from: &mut TcpStream,
+    to: &mut TcpStream,
+    mut inspect: impl FnMut(&[u8]),
+) -> io::Result<()> {
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        let read = from.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(());
+        }
+        inspect(&chunk[..read]);
+        to.write_all(&chunk[..read])?;
+    }
+}
+
+/// Decodes the values from one direction of a connection.
+/// Decoding is given up after the first error (the traffic is still forwarded).
+#[derive(Default)]
+struct Decoder {
+    buffer: Vec<u8>,
+    failed: bool,
+}
+
+impl Decoder {
+    /// Returns the values decoded from the chunk, followed by the error that stopped decoding (if any)
+    fn feed(&mut self, chunk: &[u8], allow_inline: bool) -> Vec<Result<OwnedValue, ClientError>> {
+        if self.failed {
+            return Vec::new();
+        }
+        self.buffer.extend_from_slice(chunk);
+
+        let mut values = Vec::new();
+        loop {
+            if allow_inline && self.buffer.first().is_some_and(u8::is_ascii_alphabetic) {
+                // inline command (e.g. `PING\r\n`)
+                let Some(end) = self.buffer.iter().position(|b| *b == b'\n') else {
+                    break;
+                };
+                let line = String::from_utf8_lossy(&self.buffer[..end]).into_owned();
+                self.buffer.drain(..=end);
+                values.push(Ok(OwnedValue::Array(
+                    line.split_whitespace()
+                        .map(|part| OwnedValue::BulkString(part.to_string()))
+                        .collect(),
+                )));
+                continue;
+            }
+
+            match decode_frame(&mut self.buffer) {
+                Ok(Some(value)) => values.push(Ok(value)),
+                Ok(None) => break,
+                Err(e) => {
+                    values.push(Err(e));
+                    self.failed = true;
+                    self.buffer.clear();
+                    break;
+                }
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange() -> Exchange {
+        Exchange {
+            client: "127.0.0.1:5000".parse().unwrap(),
+            request: Some(OwnedValue::Array(vec![
+                OwnedValue::BulkString("get".to_string()),
+                OwnedValue::BulkString("key".to_string()),
+            ])),
+            reply: OwnedValue::BulkString("value".to_string()),
+            latency: Some(Duration::from_micros(1500)),
+        }
+    }
+
+    #[test]
+    fn test_format_pretty() {
+        assert_eq!(
+            exchange().format(Format::Pretty),
+            "127.0.0.1:5000 GET 1.500ms\n>> 1) \"get\"\n   2) \"key\"\n<< \"value\"\n"
+        );
+    }
+
+    #[test]
+    fn test_format_json() {
+        assert_eq!(
+            exchange().format(Format::Json),
+            "{\"client\":\"127.0.0.1:5000\",\"command\":\"GET\",\"latency_ms\":1.5,\"request\":[\"get\",\"key\"],\"reply\":\"value\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_decoder() {
+        let mut decoder = Decoder::default();
+        assert_eq!(decoder.feed(b"PING\r\n*1\r\n$4\r\nPI", true).len(), 1);
+        assert_eq!(
+            decoder.feed(b"NG\r\n", true).pop().unwrap().unwrap(),
+            OwnedValue::Array(vec![OwnedValue::BulkString("PING".to_string())])
+        );
+
+        // inline commands are only allowed from clients
+        let mut decoder = Decoder::default();
+        let values = decoder.feed(b":1\r\nPING\r\n", false);
+        assert!(matches!(values[..], [Ok(OwnedValue::Integer(1)), Err(_)]));
+        assert!(decoder.failed);
+        assert!(decoder.feed(b":1\r\n", false).is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+use std::net::TcpListener;
+use std::process::ExitCode;
+use tinyresp_proxy::{Format, Proxy};
+
+const USAGE: &str = "Usage: tinyresp-proxy [--listen <addr>] [--upstream <addr>] [--json]
+
+Options:
+    --listen <addr>      The address to listen on (default: 127.0.0.1:6380)
+    --upstream <addr>    The address of the upstream server (default: 127.0.0.1:6379)
+    --json               Log the traffic as JSON (one object per line)";
+
+fn main() -> ExitCode {
+    let mut listen = "127.0.0.1:6380".to_string();
+    let mut upstream = "127.0.0.1:6379".to_string();
+    let mut format = Format::Pretty;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" | "--upstream" => {
+                let Some(value) = args.next() else {
+                    eprintln!("missing value for {}\n\n{}", arg, USAGE);
+                    return ExitCode::FAILURE;
+                };
+                if arg == "--listen" {
+                    listen = value;
+                } else {
+                    upstream = value;
+                }
+            }
+            "--json" => format = Format::Json,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                eprintln!("unknown argument: {}\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let listener = match TcpListener::bind(&listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("unable to listen on {}: {}", listen, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!("proxying {} -> {}", listen, upstream);
+    let proxy = Proxy::new(upstream, format).on_error(|e| eprintln!("{}", e));
+    if let Err(e) = proxy.serve(listener) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
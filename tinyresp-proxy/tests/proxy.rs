@@ -0,0 +1,181 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tinyresp_proxy::{Format, Proxy, ProxyError, MAX_PENDING_REQUESTS};
+
+/// A log writer that can be inspected by the test
+#[derive(Clone, Default)]
+struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+impl SharedLog {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+
+    /// Waits until the log contains the given number of lines
+    fn wait_lines(&self, lines: usize) -> String {
+        let start = Instant::now();
+        loop {
+            let contents = self.contents();
+            if contents.lines().count() >= lines || start.elapsed() > Duration::from_secs(5) {
+                return contents;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Write for SharedLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Starts a fake upstream server replying to every request with the given bytes
+fn fake_upstream(replies: &'static [&'static [u8]]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = [0u8; 1024];
+        for reply in replies {
+            if stream.read(&mut buffer).unwrap() == 0 {
+                return;
+            }
+            stream.write_all(reply).unwrap();
+        }
+    });
+    addr
+}
+
+fn start_proxy(upstream: String, format: Format) -> (TcpStream, SharedLog) {
+    let (client, log, _errors) = start_proxy_with_errors(upstream, format);
+    (client, log)
+}
+
+/// Starts a proxy collecting the errors it reports
+fn start_proxy_with_errors(
+    upstream: String,
+    format: Format,
+) -> (TcpStream, SharedLog, Arc<Mutex<Vec<String>>>) {
+    let log = SharedLog::default();
+    let errors: Arc<Mutex<Vec<String>>> = Default::default();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let proxy = Proxy::new(upstream, format).log_to(log.clone()).on_error({
+        let errors = Arc::clone(&errors);
+        move |e: &ProxyError| errors.lock().unwrap().push(e.to_string())
+    });
+    thread::spawn(move || proxy.serve(listener));
+    (TcpStream::connect(addr).unwrap(), log, errors)
+}
+
+fn read_exactly(stream: &mut TcpStream, len: usize) -> Vec<u8> {
+    let mut buffer = vec![0u8; len];
+    stream.read_exact(&mut buffer).unwrap();
+    buffer
+}
+
+#[test]
+fn test_forwards_and_logs_json() {
+    let upstream = fake_upstream(&[b"+OK\r\n", b"$5\r\nhello\r\n"]);
+    let (mut client, log) = start_proxy(upstream, Format::Json);
+
+    client
+        .write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nhello\r\n")
+        .unwrap();
+    assert_eq!(read_exactly(&mut client, 5), b"+OK\r\n");
+    client.write_all(b"GET key\r\n").unwrap();
+    assert_eq!(read_exactly(&mut client, 11), b"$5\r\nhello\r\n");
+
+    let contents = log.wait_lines(2);
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("\"command\":\"SET\""));
+    assert!(lines[0].contains("\"latency_ms\":"));
+    assert!(lines[0].contains("\"request\":[\"SET\",\"key\",\"hello\"],\"reply\":\"OK\"}"));
+    assert!(lines[1].contains("\"command\":\"GET\""));
+    assert!(lines[1].contains("\"request\":[\"GET\",\"key\"],\"reply\":\"hello\"}"));
+}
+
+#[test]
+fn test_logs_pushes_pretty() {
+    // the confirmation of SUBSCRIBE is followed by a message in the same write
+    const REPLY: &[u8] =
+        b">3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n>3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n";
+    let upstream = fake_upstream(&[REPLY]);
+    let (mut client, log) = start_proxy(upstream, Format::Pretty);
+
+    client
+        .write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$4\r\nnews\r\n")
+        .unwrap();
+    assert_eq!(read_exactly(&mut client, REPLY.len()), REPLY);
+
+    let contents = log.wait_lines(10);
+    let lines: Vec<&str> = contents.lines().collect();
+    // the confirmation is paired to the request
+    assert!(lines[0].contains(" SUBSCRIBE "));
+    assert!(lines[0].ends_with("ms"));
+    assert_eq!(
+        lines[1..6],
+        [
+            ">> 1) \"SUBSCRIBE\"",
+            "   2) \"news\"",
+            "<< 1) \"subscribe\"",
+            "   2) \"news\"",
+            "   3) (integer) 1",
+        ]
+    );
+    // the message is logged on its own
+    assert!(lines[6].ends_with(" PUSH"));
+    assert_eq!(
+        lines[7..],
+        ["<< 1) \"message\"", "   2) \"news\"", "   3) \"hi\""]
+    );
+}
+
+#[test]
+fn test_stops_logging_after_a_decode_error() {
+    let upstream = fake_upstream(&[b"?bad\r\n", b"+OK\r\n"]);
+    let (mut client, log, errors) = start_proxy_with_errors(upstream, Format::Json);
+
+    // the traffic is still forwarded
+    client.write_all(b"PING\r\n").unwrap();
+    assert_eq!(read_exactly(&mut client, 6), b"?bad\r\n");
+    client.write_all(b"PING\r\n").unwrap();
+    assert_eq!(read_exactly(&mut client, 5), b"+OK\r\n");
+
+    assert_eq!(log.contents(), "");
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("unable to decode the traffic of 127.0.0.1:"));
+}
+
+#[test]
+fn test_limits_pending_requests() {
+    // an upstream server that never replies
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        io::copy(&mut stream, &mut io::sink()).unwrap();
+    });
+    let (mut client, _log, errors) = start_proxy_with_errors(upstream, Format::Json);
+
+    client
+        .write_all(&b"PING\n".repeat(MAX_PENDING_REQUESTS + 1))
+        .unwrap();
+    let start = Instant::now();
+    while errors.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("too many pending requests from 127.0.0.1:"));
+}